@@ -0,0 +1,530 @@
+use std::{env, fs, path::Path, path::PathBuf};
+
+// the checks validate.rs runs, so imported models are held to exactly the same rules
+#[path = "src/mesh_check.rs"]
+mod mesh_check;
+use mesh_check::Issue;
+
+// so quantised models come out the same as QuantisedMesh::from_mesh makes them
+#[path = "src/quantised_raw.rs"]
+mod quantised_raw;
+
+// the scene file layout, shared with scene_file.rs so both sides read and write exactly the same bytes. obj
+// scenes only ever use one shading and cull mode, the other codes are for the console side
+#[path = "src/scene_format.rs"]
+#[allow(dead_code)]
+mod scene_format;
+use scene_format::*;
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    heightmaps(Path::new(&out_dir));
+    textures(Path::new(&out_dir));
+    scenes(Path::new(&out_dir));
+}
+
+// every file with this extension in an assets folder, making sure cargo reruns when any of them change
+fn assets(source: &Path, extension: &str) -> Vec<PathBuf> {
+    println!("cargo:rerun-if-changed={}", source.display());
+    let entries = match fs::read_dir(source) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(extension) {
+            continue;
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+        paths.push(path);
+    }
+    paths
+}
+
+// turns every grayscale png in assets/heightmaps into a raw 8 bit heightmap that include_heightmap! can embed.
+// the output is the width and height as little endian u16s followed by one byte per pixel, row by row.
+fn heightmaps(out_dir: &Path) {
+    let dest = out_dir.join("heightmaps");
+    fs::create_dir_all(&dest).unwrap();
+
+    for path in assets(Path::new("assets/heightmaps"), "png") {
+        let mut decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+        // paletted, low bit depth and 16 bit images all come out as 8 bits per channel
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        let channels = info.color_type.samples();
+        if info.width > u16::MAX as u32 || info.height > u16::MAX as u32 {
+            panic!("{} is too big for a heightmap.", path.display());
+        }
+
+        let mut output = Vec::with_capacity(4 + (info.width * info.height) as usize);
+        output.extend_from_slice(&(info.width as u16).to_le_bytes());
+        output.extend_from_slice(&(info.height as u16).to_le_bytes());
+        for pixel in pixels[..info.buffer_size()].chunks(channels) {
+            // colour images get averaged down to gray, any alpha channel is ignored
+            let colour = if channels >= 3 {
+                &pixel[..3]
+            } else {
+                &pixel[..1]
+            };
+            let total: u32 = colour.iter().map(|&c| c as u32).sum();
+            output.push((total / colour.len() as u32) as u8);
+        }
+
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        fs::write(dest.join(format!("{name}.bin")), output).unwrap();
+    }
+}
+
+// turns every indexed png in assets/textures into a texture that include_texture! can embed. the output is
+// the width, height and palette length as little endian u16s, the palette as Rgb15, then one index per texel.
+fn textures(out_dir: &Path) {
+    let dest = out_dir.join("textures");
+    fs::create_dir_all(&dest).unwrap();
+
+    for path in assets(Path::new("assets/textures"), "png") {
+        let mut decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+        // keep the palette indicies rather than expanding them to colours
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        if info.color_type != png::ColorType::Indexed {
+            panic!(
+                "{} has to be an indexed png to use as a texture.",
+                path.display()
+            );
+        }
+        if !info.width.is_power_of_two() || !info.height.is_power_of_two() {
+            panic!("{} has to be a power of two wide and high.", path.display());
+        }
+        if info.width > 256 || info.height > 256 {
+            panic!("{} is too big for a texture.", path.display());
+        }
+
+        let palette = reader.info().palette.as_ref().unwrap();
+        let colors = palette.len() / 3;
+        let mut output = Vec::with_capacity(6 + colors * 2 + (info.width * info.height) as usize);
+        output.extend_from_slice(&(info.width as u16).to_le_bytes());
+        output.extend_from_slice(&(info.height as u16).to_le_bytes());
+        output.extend_from_slice(&(colors as u16).to_le_bytes());
+        for rgb in palette.chunks(3) {
+            let colour =
+                (rgb[0] as u16 >> 3) | ((rgb[1] as u16 >> 3) << 5) | ((rgb[2] as u16 >> 3) << 10);
+            output.extend_from_slice(&colour.to_le_bytes());
+        }
+
+        // 1, 2 and 4 bit images pack several texels into a byte, leftmost in the high bits
+        let bits = info.bit_depth as usize;
+        let mask = ((1u16 << bits) - 1) as u8;
+        for row in pixels[..info.buffer_size()].chunks(info.line_size) {
+            for x in 0..info.width as usize {
+                let bit = x * bits;
+                let shift = 8 - bits - bit % 8;
+                output.push((row[bit / 8] >> shift) & mask);
+            }
+        }
+
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        fs::write(dest.join(format!("{name}.bin")), output).unwrap();
+    }
+}
+
+// one o block of an obj file, positions are already in the engine's coordinates (y down, z into the screen)
+#[derive(Default)]
+struct Object {
+    // palette colors in the order usemtl first picked them, faces and lines index into this
+    materials: Vec<u8>,
+    // material and corners, each corner a position and maybe a uv
+    faces: Vec<(u8, [([f64; 3], Option<[f64; 2]>); 3])>,
+    lines: Vec<(u8, [[f64; 3]; 2])>,
+}
+
+impl Object {
+    fn material(&mut self, color: u8) -> u8 {
+        let index = match self.materials.iter().position(|&c| c == color) {
+            Some(index) => index,
+            None => {
+                self.materials.push(color);
+                self.materials.len() - 1
+            }
+        };
+        index as u8
+    }
+
+    // the middle of the bounds, objects get placed here so they spin about their own center
+    fn center(&self) -> [f64; 3] {
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for p in self.points() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        [0, 1, 2].map(|axis| (min[axis] + max[axis]) / 2.0)
+    }
+
+    fn points(&self) -> impl Iterator<Item = [f64; 3]> + '_ {
+        self.faces
+            .iter()
+            .flat_map(|(_, corners)| corners.iter().map(|corner| corner.0))
+            .chain(self.lines.iter().flat_map(|(_, ends)| ends.iter().copied()))
+    }
+}
+
+// unit length, the same way mesh! works it out
+fn face_normal(corners: &[([f64; 3], Option<[f64; 2]>); 3]) -> [f64; 3] {
+    let [a, b, c] = corners.map(|corner| corner.0);
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    let length = if length == 0.0 { 1.0 } else { length };
+    n.map(|n| n / length)
+}
+
+fn bad_obj(path: &Path, line: usize, what: &str) -> ! {
+    panic!("{}:{}: {}", path.display(), line + 1, what);
+}
+
+// obj indicies start at 1, negative ones count back from the latest
+fn obj_index(path: &Path, line: usize, word: &str, count: usize) -> usize {
+    let index = match word.parse::<i64>() {
+        Ok(index) if index > 0 => index - 1,
+        Ok(index) if index < 0 => count as i64 + index,
+        _ => bad_obj(path, line, "bad index"),
+    };
+    if index < 0 || index as usize >= count {
+        bad_obj(path, line, "index out of range");
+    }
+    index as usize
+}
+
+fn obj_numbers<const N: usize>(path: &Path, line: usize, words: &[&str]) -> [f64; N] {
+    let mut numbers = [0.0; N];
+    for (i, number) in numbers.iter_mut().enumerate() {
+        *number = match words.get(i).map(|word| word.parse()) {
+            Some(Ok(n)) => n,
+            _ => bad_obj(path, line, "expected a number"),
+        };
+    }
+    numbers
+}
+
+// the supported part of the obj format: v, vt, f (fanned into triangles), l, o and usemtl. usemtl names are
+// palette colors, each becomes a single color material. normals are worked out from the winding like mesh!
+// does rather than read from the file
+fn read_obj(path: &Path) -> Vec<Object> {
+    let text = fs::read_to_string(path).unwrap();
+    let mut positions: Vec<[f64; 3]> = Vec::new();
+    let mut uvs: Vec<[f64; 2]> = Vec::new();
+    let mut objects = vec![Object::default()];
+    let mut color = 0;
+    for (number, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&kind, words)) = words.split_first() else {
+            continue;
+        };
+        let object = objects.last_mut().unwrap();
+        match kind {
+            "v" => positions.push(obj_numbers(path, number, words)),
+            "vt" => {
+                // obj puts v = 0 at the bottom of the image, textures here start at the top
+                let [u, v] = obj_numbers(path, number, words);
+                uvs.push([u, 1.0 - v]);
+            }
+            "o" => {
+                if !object.faces.is_empty() || !object.lines.is_empty() {
+                    objects.push(Object::default());
+                }
+            }
+            "usemtl" => {
+                color = match words.first().map(|word| word.parse()) {
+                    Some(Ok(color)) => color,
+                    _ => bad_obj(path, number, "usemtl needs a palette color from 0 to 255"),
+                }
+            }
+            "f" => {
+                let corners: Vec<([f64; 3], Option<[f64; 2]>)> = words
+                    .iter()
+                    .map(|word| {
+                        let mut parts = word.split('/');
+                        let position =
+                            obj_index(path, number, parts.next().unwrap(), positions.len());
+                        let uv = match parts.next() {
+                            Some(uv) if !uv.is_empty() => {
+                                Some(uvs[obj_index(path, number, uv, uvs.len())])
+                            }
+                            _ => None,
+                        };
+                        (positions[position], uv)
+                    })
+                    .collect();
+                if corners.len() < 3 {
+                    bad_obj(path, number, "faces need at least 3 corners");
+                }
+                let material = object.material(color);
+                for i in 1..corners.len() - 1 {
+                    object
+                        .faces
+                        .push((material, [corners[0], corners[i], corners[i + 1]]));
+                }
+            }
+            "l" => {
+                let ends: Vec<[f64; 3]> = words
+                    .iter()
+                    .map(|word| {
+                        let position = word.split('/').next().unwrap();
+                        positions[obj_index(path, number, position, positions.len())]
+                    })
+                    .collect();
+                for pair in ends.windows(2) {
+                    object.lines.push((color, [pair[0], pair[1]]));
+                }
+            }
+            _ => {}
+        }
+    }
+    objects.retain(|object| !object.faces.is_empty() || !object.lines.is_empty());
+    objects
+}
+
+// the same checks as validate.rs, so a broken model stops the build instead of drawing wrong on the console.
+// positions are compared as the DefaultNums they'll become. open and non-manifold edges are only warnings,
+// a flat floor or two blocks touching at a corner are fine
+fn check_object(path: &Path, index: usize, object: &Object) {
+    let raw = |p: [f64; 3]| p.map(|n| (n * 4096.0) as i32);
+    let triangles: Vec<[[i32; 3]; 3]> = object
+        .faces
+        .iter()
+        .map(|(_, corners)| corners.map(|corner| raw(corner.0)))
+        .collect();
+
+    let mut errors = Vec::new();
+    let mut open = 0;
+    let mut non_manifold = 0;
+    for issue in mesh_check::check(&triangles) {
+        if !issue.is_error() {
+            match issue {
+                Issue::OpenEdge(_) => open += 1,
+                _ => non_manifold += 1,
+            }
+            continue;
+        }
+        errors.push(match issue {
+            Issue::ZeroArea(face) => format!("triangle {face} has no area"),
+            Issue::DuplicateFace(first, face) => {
+                format!("triangles {first} and {face} are the same")
+            }
+            Issue::InconsistentWinding(a, b) => {
+                format!("triangles {a} and {b} are wound opposite ways")
+            }
+            other => format!("{other:?}"),
+        });
+    }
+
+    let name = format!("{} object {index}", path.display());
+    if open > 0 {
+        println!("cargo:warning={name} has {open} open edges");
+    }
+    if non_manifold > 0 {
+        println!("cargo:warning={name} has {non_manifold} edges shared by more than two triangles");
+    }
+    if !errors.is_empty() {
+        panic!("{name} can't be imported:\n{}", errors.join("\n"));
+    }
+}
+
+// unlit, back faces culled, each material a ramp of just its color. read straight back afterwards, so a file
+// the console can't load fails here instead
+fn write_scene(path: &Path, objects: &[Object]) -> Vec<u8> {
+    // DefaultNum has 12 fractional bits, rounded the same way const_num does it
+    let vector = |v: [f64; 3]| v.map(|n| (n * 4096.0) as i32);
+
+    let mut meshes = Vec::new();
+    let mut placed = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        let center = object.center();
+        let local = |p: [f64; 3]| vector([0, 1, 2].map(|axis| p[axis] - center[axis]));
+        let textured = object
+            .faces
+            .iter()
+            .any(|(_, corners)| corners.iter().any(|c| c.1.is_some()));
+        meshes.push(RawMesh {
+            polygons: object
+                .faces
+                .iter()
+                .map(|(material, corners)| RawPolygon {
+                    material: *material,
+                    verticies: corners.map(|corner| local(corner.0)),
+                    normal: vector(face_normal(corners)),
+                })
+                .collect(),
+            lines: object
+                .lines
+                .iter()
+                .map(|(color, ends)| RawLine {
+                    color: *color,
+                    verticies: ends.map(local),
+                })
+                .collect(),
+            uvs: textured.then(|| {
+                object
+                    .faces
+                    .iter()
+                    .map(|(_, corners)| {
+                        corners.map(|corner| {
+                            corner.1.unwrap_or([0.0, 0.0]).map(|n| (n * 4096.0) as i32)
+                        })
+                    })
+                    .collect()
+            }),
+            materials: object
+                .materials
+                .iter()
+                .map(|color| RawMaterial {
+                    shading: SHADING_UNLIT,
+                    cull: CULL_BACK,
+                    ramp: std::slice::from_ref(color),
+                })
+                .collect(),
+            rgbs: None,
+        });
+        placed.push(RawObject {
+            // more objects than fit in a u16 and write turns the file down anyway
+            mesh: i as u16,
+            pos: vector(center),
+            rot: [0; 3],
+            scale: vector([1.0, 1.0, 1.0]),
+        });
+    }
+
+    let scene = RawScene {
+        camera_pos: vector([0.0, 0.0, -160.0]),
+        camera_rot: [0; 3],
+        meshes,
+        objects: placed,
+    };
+    let output = match scene.write() {
+        Ok(output) => output,
+        Err(error) => panic!(
+            "{} can't be written as a scene file: {error:?}",
+            path.display()
+        ),
+    };
+    assert_eq!(
+        RawScene::read(&output).as_ref(),
+        Ok(&scene),
+        "{} doesn't read back the same",
+        path.display()
+    );
+    output
+}
+
+// the same objects as rust that include_quantised! embeds: an array with a QuantisedMesh<i16> for each object,
+// placed where the scene places it. the polygons end up in statics, so they stay in rom
+fn write_quantised(path: &Path, objects: &[Object]) -> String {
+    let raw = |n: f64| (n * 4096.0) as i32;
+    let vector = |v: [i32; 3]| {
+        format!(
+            "Vector3D::new(DefaultNum::from_raw({}), DefaultNum::from_raw({}), DefaultNum::from_raw({}))",
+            v[0], v[1], v[2]
+        )
+    };
+
+    let mut meshes = String::new();
+    for (index, object) in objects.iter().enumerate() {
+        let center = object.center();
+        let local = |p: [f64; 3]| [0, 1, 2].map(|axis| raw(p[axis] - center[axis]));
+        let mut min = [i32::MAX; 3];
+        let mut max = [i32::MIN; 3];
+        for p in object.points().map(local) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        let offset = [0, 1, 2].map(|axis| quantised_raw::center(min[axis], max[axis]));
+        let reach = (0..3).map(|axis| max[axis] - offset[axis]).max().unwrap();
+        let step = quantised_raw::step(reach, i16::MAX as i32);
+
+        let mut polygons = String::new();
+        for (face, (material, corners)) in object.faces.iter().enumerate() {
+            let verticies = corners.map(|corner| {
+                let p = local(corner.0);
+                [0, 1, 2].map(|axis| quantised_raw::position(p[axis], offset[axis], step))
+            });
+            let normal = face_normal(corners).map(|n| quantised_raw::normal(raw(n)));
+            let uv = corners.map(|corner| {
+                corner.1.unwrap_or([0.0, 0.0]).map(|n| match quantised_raw::uv(raw(n)) {
+                    Some(n) => n,
+                    None => panic!(
+                        "{} object {index} triangle {face} has texture coordinates past -128 to 128.",
+                        path.display()
+                    ),
+                })
+            });
+            polygons.push_str(&format!(
+                "QuantisedPolygon {{ material: {material}, verticies: {verticies:?}, normal: {normal:?}, uv: {uv:?} }},\n"
+            ));
+        }
+        let materials: String = object
+            .materials
+            .iter()
+            .map(|color| format!("Material::solid(&[{color}]),"))
+            .collect();
+
+        meshes.push_str(&format!(
+            "{{\n\
+             static POLYGONS: [QuantisedPolygon<i16>; {}] = [\n{polygons}];\n\
+             static MATERIALS: [Material; {}] = [{materials}];\n\
+             let mut mesh = QuantisedMesh::from_static(&POLYGONS, &MATERIALS, DefaultNum::from_raw({step}), {});\n\
+             mesh.pos = {};\n\
+             mesh\n\
+             }},\n",
+            object.faces.len(),
+            object.materials.len(),
+            vector(offset),
+            vector(center.map(raw)),
+        ));
+    }
+    format!("[\n{meshes}]\n")
+}
+
+// turns every obj model in assets/scenes into a scene file that include_scene! can embed, each o block in the
+// file becomes its own object. a quantised copy for include_quantised! gets written alongside
+fn scenes(out_dir: &Path) {
+    let dest = out_dir.join("scenes");
+    fs::create_dir_all(&dest).unwrap();
+    let quantised = out_dir.join("quantised");
+    fs::create_dir_all(&quantised).unwrap();
+
+    for path in assets(Path::new("assets/scenes"), "obj") {
+        let objects = read_obj(&path);
+        if objects.is_empty() {
+            panic!("{} doesn't have anything in it.", path.display());
+        }
+        for (index, object) in objects.iter().enumerate() {
+            check_object(&path, index, object);
+        }
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        fs::write(
+            dest.join(format!("{name}.scene")),
+            write_scene(&path, &objects),
+        )
+        .unwrap();
+        fs::write(
+            quantised.join(format!("{name}.rs")),
+            write_quantised(&path, &objects),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+extern crate alloc;
+use alloc::borrow::Cow;
+
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+// counts frames since startup, tick it once per vblank and everything animated stays in step
+pub struct FrameClock {
+    pub frame: u32,
+}
+
+impl FrameClock {
+    pub fn new() -> FrameClock {
+        FrameClock { frame: 0 }
+    }
+
+    pub fn tick(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // catmull-rom, passes through every keyframe
+    Cubic,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+    // holds the last keyframe once it gets there
+    Once,
+    Loop,
+    // plays forwards then backwards
+    PingPong,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub frame: u32,
+    pub value: Vector3D<DefaultNum>,
+}
+
+pub struct Track {
+    // sorted by frame
+    pub keyframes: Cow<'static, [Keyframe]>,
+    pub interpolation: Interpolation,
+}
+
+impl Track {
+    pub fn new(
+        keyframes: impl Into<Cow<'static, [Keyframe]>>,
+        interpolation: Interpolation,
+    ) -> Track {
+        Track {
+            keyframes: keyframes.into(),
+            interpolation,
+        }
+    }
+
+    pub fn length(&self) -> u32 {
+        self.keyframes.last().map_or(0, |key| key.frame)
+    }
+
+    pub fn sample(&self, frame: u32) -> Vector3D<DefaultNum> {
+        let keys = &self.keyframes;
+        if keys.is_empty() {
+            panic!("Track has no keyframes.");
+        }
+        // index of the first keyframe after this frame
+        let next = keys.iter().position(|key| key.frame > frame);
+        let next = match next {
+            Some(0) => return keys[0].value,
+            Some(next) => next,
+            None => return keys[keys.len() - 1].value,
+        };
+        let (a, b) = (keys[next - 1], keys[next]);
+        let t = div(
+            DefaultNum::new((frame - a.frame) as i32),
+            DefaultNum::new((b.frame - a.frame) as i32),
+        );
+
+        match self.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => a.value + (b.value - a.value) * t,
+            Interpolation::Cubic => {
+                // the keyframes either side of the pair, repeating the ends
+                let before = keys[next.saturating_sub(2)].value;
+                let after = keys[(next + 1).min(keys.len() - 1)].value;
+                catmull_rom(before, a.value, b.value, after, t)
+            }
+        }
+    }
+}
+
+fn catmull_rom(
+    p0: Vector3D<DefaultNum>,
+    p1: Vector3D<DefaultNum>,
+    p2: Vector3D<DefaultNum>,
+    p3: Vector3D<DefaultNum>,
+    t: DefaultNum,
+) -> Vector3D<DefaultNum> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let a = p1 * num!(2.0);
+    let b = p2 - p0;
+    let c = p0 * num!(2.0) - p1 * num!(5.0) + p2 * num!(4.0) - p3;
+    let d = p1 * num!(3.0) - p0 - p2 * num!(3.0) + p3;
+    (a + b * t + c * t2 + d * t3) * num!(0.5)
+}
+
+// any track left as None leaves that part of the mesh alone
+pub struct Animation {
+    pub position: Option<Track>,
+    pub rotation: Option<Track>,
+    pub scale: Option<Track>,
+    pub mode: PlayMode,
+    // clock frame the animation started on
+    pub start: u32,
+}
+
+impl Animation {
+    pub fn new(mode: PlayMode) -> Animation {
+        Animation {
+            position: None,
+            rotation: None,
+            scale: None,
+            mode,
+            start: 0,
+        }
+    }
+
+    pub fn play(&mut self, clock: &FrameClock) {
+        self.start = clock.frame;
+    }
+
+    pub fn length(&self) -> u32 {
+        [&self.position, &self.rotation, &self.scale]
+            .iter()
+            .filter_map(|track| track.as_ref().map(Track::length))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // where in the animation the clock is, after looping or bouncing
+    pub fn local_frame(&self, clock: &FrameClock) -> u32 {
+        let elapsed = clock.frame.wrapping_sub(self.start);
+        let length = self.length();
+        if length == 0 {
+            return 0;
+        }
+        match self.mode {
+            PlayMode::Once => elapsed.min(length),
+            PlayMode::Loop => elapsed % length,
+            PlayMode::PingPong => {
+                let frame = elapsed % (length * 2);
+                if frame > length {
+                    length * 2 - frame
+                } else {
+                    frame
+                }
+            }
+        }
+    }
+
+    pub fn apply(&self, clock: &FrameClock, mesh: &mut Mesh) {
+        let frame = self.local_frame(clock);
+        if let Some(track) = &self.position {
+            mesh.pos = track.sample(frame);
+        }
+        if let Some(track) = &self.rotation {
+            mesh.rot = track.sample(frame);
+        }
+        if let Some(track) = &self.scale {
+            mesh.scale = track.sample(frame);
+        }
+    }
+}
@@ -0,0 +1,367 @@
+/*
+the different bitmap modes the gba can draw into. the direct color modes take Rgb15s straight from any
+material that has one, so shaded polygons aren't held to 256 colors. everything else is still drawn with 8 bit
+colors, which they look up in a table of Rgb15s as it's plotted. that table isn't the hardware palette, so it
+can hold smooth ramps without fighting anything else for palette space.
+
+    mode 4: 240x160, 256 palette colors, two pages
+    mode 3: 240x160, any color, one page so drawing can be seen happening
+    mode 5: 160x128, any color, two pages. BG2's affine transform stretches it to fill the screen
+*/
+
+use core::marker::PhantomData;
+use core::ptr::{read_volatile, write_volatile};
+
+use agb::display::{bitmap3::Bitmap3, bitmap4::Bitmap4, video::Video};
+use agb::fixnum::{num, Vector2D};
+
+use crate::drawing::*;
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Rgb15(pub u16);
+
+impl Rgb15 {
+    // each channel is 0 to 31
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb15 {
+        Rgb15((r as u16 & 31) | ((g as u16 & 31) << 5) | ((b as u16 & 31) << 10))
+    }
+
+    // every channel times brightness, 0 is black and 1 leaves it as it is
+    pub fn scaled(self, brightness: DefaultNum) -> Rgb15 {
+        let brightness = brightness.clamp(num!(0.0), num!(1.0));
+        let channel = |shift: u16| {
+            let value = DefaultNum::new(((self.0 >> shift) & 31) as i32) * brightness;
+            (value + num!(0.5)).floor() as u8
+        };
+        Rgb15::new(channel(0), channel(5), channel(10))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    Mode4,
+    Mode3,
+    Mode5,
+}
+
+impl Backend {
+    pub fn size(&self) -> (i32, i32) {
+        match self {
+            Backend::Mode4 | Backend::Mode3 => (240, 160),
+            Backend::Mode5 => (160, 128),
+        }
+    }
+
+    // how much smaller than the screen each axis is drawn, so things keep their shape once it's stretched
+    pub fn pixel_scale(&self) -> Vector2D<DefaultNum> {
+        match self {
+            Backend::Mode4 | Backend::Mode3 => Vector2D::new(num!(1.0), num!(1.0)),
+            Backend::Mode5 => Vector2D::new(num!(0.6667), num!(0.8)),
+        }
+    }
+}
+
+// mode 3's one page, Bitmap3 draws a pixel at a time so clear writes here itself
+const MODE3_VRAM: usize = 0x0600_0000;
+
+pub struct Mode3<'gba> {
+    pub bitmap: Bitmap3<'gba>,
+    // what each 8 bit color turns into
+    pub palette: [Rgb15; 256],
+}
+
+impl Mode3<'_> {
+    pub fn clear(&mut self, color: u8) {
+        let color = self.palette[color as usize].0 as u32;
+        let base = MODE3_VRAM as *mut u32;
+        // two pixels a write
+        for i in 0..240 * 160 / 2 {
+            unsafe { write_volatile(base.add(i), color | (color << 16)) };
+        }
+    }
+
+    fn fill<C: Copy>(&mut self, tri: &Triangle2D<DefaultNum>, color: C)
+    where
+        Self: Plot<C>,
+    {
+        static mut X_BOUNDS: [usize; 160] = [0; 160];
+        // only ever touched from here, and there's only one thread
+        let bounds = unsafe { &mut *core::ptr::addr_of_mut!(X_BOUNDS) };
+        crate::drawing::tri(self, bounds, tri, color);
+    }
+}
+
+impl Plot for Mode3<'_> {
+    fn width(&self) -> i32 {
+        240
+    }
+    fn height(&self) -> i32 {
+        160
+    }
+    fn plot(&mut self, x: i32, y: i32, color: u8) {
+        self.bitmap.draw_point(x, y, self.palette[color as usize].0);
+    }
+}
+
+impl Plot<Rgb15> for Mode3<'_> {
+    fn width(&self) -> i32 {
+        240
+    }
+    fn height(&self) -> i32 {
+        160
+    }
+    fn plot(&mut self, x: i32, y: i32, color: Rgb15) {
+        self.bitmap.draw_point(x, y, color.0);
+    }
+}
+
+impl Draw for Mode3<'_> {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        dot(self, p, color);
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        line(self, a, b, color);
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        self.fill(tri, color);
+    }
+    fn draw_tri_rgb(
+        &mut self,
+        tri: &Triangle2D<DefaultNum>,
+        _z: [DefaultNum; 3],
+        color: Rgb15,
+        _index: u8,
+    ) {
+        self.fill(tri, color);
+    }
+}
+
+const DISPCNT: *mut u16 = 0x0400_0000 as *mut u16;
+const BG2PA: *mut i16 = 0x0400_0020 as *mut i16;
+const BG2PB: *mut i16 = 0x0400_0022 as *mut i16;
+const BG2PC: *mut i16 = 0x0400_0024 as *mut i16;
+const BG2PD: *mut i16 = 0x0400_0026 as *mut i16;
+const BG2X: *mut i32 = 0x0400_0028 as *mut i32;
+const BG2Y: *mut i32 = 0x0400_002C as *mut i32;
+const MODE5_PAGES: [usize; 2] = [0x0600_0000, 0x0600_A000];
+
+// agb has no mode 5 bitmap, so this drives the registers itself. borrowing the video stops anything else
+// changing modes underneath it
+pub struct Mode5<'gba> {
+    // the page being drawn to, the other one is on screen
+    page: usize,
+    pub palette: [Rgb15; 256],
+    phantom: PhantomData<&'gba mut Video>,
+}
+
+impl<'gba> Mode5<'gba> {
+    pub fn new(_video: &'gba mut Video) -> Mode5<'gba> {
+        let mut mode5 = Mode5 {
+            page: 1,
+            palette: [Rgb15(0); 256],
+            phantom: PhantomData,
+        };
+        // 8.8 fixed point steps through the bitmap per screen pixel, 160/240 across and 128/160 down
+        unsafe {
+            write_volatile(BG2PA, 171);
+            write_volatile(BG2PB, 0);
+            write_volatile(BG2PC, 0);
+            write_volatile(BG2PD, 205);
+            write_volatile(BG2X, 0);
+            write_volatile(BG2Y, 0);
+        }
+        mode5.show(0);
+        mode5
+    }
+
+    fn show(&mut self, page: usize) {
+        // mode 5, page select, BG2 on
+        unsafe { write_volatile(DISPCNT, 5 | ((page as u16) << 4) | (1 << 10)) };
+    }
+
+    fn pixel(&self, x: i32, y: i32) -> *mut u16 {
+        (MODE5_PAGES[self.page] + (y as usize * 160 + x as usize) * 2) as *mut u16
+    }
+
+    pub fn clear(&mut self, color: u8) {
+        let color = self.palette[color as usize].0 as u32;
+        let base = MODE5_PAGES[self.page] as *mut u32;
+        // two pixels a write
+        for i in 0..160 * 128 / 2 {
+            unsafe { write_volatile(base.add(i), color | (color << 16)) };
+        }
+    }
+
+    pub fn flip_page(&mut self) {
+        self.show(self.page);
+        self.page = 1 - self.page;
+    }
+
+    pub fn read_point(&self, x: i32, y: i32) -> Rgb15 {
+        Rgb15(unsafe { read_volatile(self.pixel(x, y)) })
+    }
+
+    fn fill<C: Copy>(&mut self, tri: &Triangle2D<DefaultNum>, color: C)
+    where
+        Self: Plot<C>,
+    {
+        static mut X_BOUNDS: [usize; 128] = [0; 128];
+        // only ever touched from here, and there's only one thread
+        let bounds = unsafe { &mut *core::ptr::addr_of_mut!(X_BOUNDS) };
+        crate::drawing::tri(self, bounds, tri, color);
+    }
+}
+
+impl Plot for Mode5<'_> {
+    fn width(&self) -> i32 {
+        160
+    }
+    fn height(&self) -> i32 {
+        128
+    }
+    fn plot(&mut self, x: i32, y: i32, color: u8) {
+        unsafe { write_volatile(self.pixel(x, y), self.palette[color as usize].0) };
+    }
+}
+
+impl Plot<Rgb15> for Mode5<'_> {
+    fn width(&self) -> i32 {
+        160
+    }
+    fn height(&self) -> i32 {
+        128
+    }
+    fn plot(&mut self, x: i32, y: i32, color: Rgb15) {
+        unsafe { write_volatile(self.pixel(x, y), color.0) };
+    }
+}
+
+impl Draw for Mode5<'_> {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        dot(self, p, color);
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        line(self, a, b, color);
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        self.fill(tri, color);
+    }
+    fn draw_tri_rgb(
+        &mut self,
+        tri: &Triangle2D<DefaultNum>,
+        _z: [DefaultNum; 3],
+        color: Rgb15,
+        _index: u8,
+    ) {
+        self.fill(tri, color);
+    }
+}
+
+// whichever bitmap the renderer's backend asked for
+pub enum Screen<'gba> {
+    Mode4(Bitmap4<'gba>),
+    Mode3(Mode3<'gba>),
+    Mode5(Mode5<'gba>),
+}
+
+impl<'gba> Screen<'gba> {
+    pub fn new(video: &'gba mut Video, backend: Backend) -> Screen<'gba> {
+        match backend {
+            Backend::Mode4 => Screen::Mode4(video.bitmap4()),
+            Backend::Mode3 => Screen::Mode3(Mode3 {
+                bitmap: video.bitmap3(),
+                palette: [Rgb15(0); 256],
+            }),
+            Backend::Mode5 => Screen::Mode5(Mode5::new(video)),
+        }
+    }
+
+    pub fn set_palette_entry(&mut self, index: u8, color: Rgb15) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.set_palette_entry(index as u32, color.0),
+            Screen::Mode3(mode3) => mode3.palette[index as usize] = color,
+            Screen::Mode5(mode5) => mode5.palette[index as usize] = color,
+        }
+    }
+
+    pub fn clear(&mut self, color: u8) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.clear(color),
+            Screen::Mode3(mode3) => mode3.clear(color),
+            Screen::Mode5(mode5) => mode5.clear(color),
+        }
+    }
+
+    // shows what's been drawn this frame, call after waiting for vblank
+    pub fn present(&mut self) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.flip_page(),
+            // there's only one page and it's always on screen
+            Screen::Mode3(_) => {}
+            Screen::Mode5(mode5) => mode5.flip_page(),
+        }
+    }
+}
+
+impl Plot for Screen<'_> {
+    fn width(&self) -> i32 {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.width(),
+            Screen::Mode3(mode3) => Plot::<u8>::width(mode3),
+            Screen::Mode5(mode5) => Plot::<u8>::width(mode5),
+        }
+    }
+    fn height(&self) -> i32 {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.height(),
+            Screen::Mode3(mode3) => Plot::<u8>::height(mode3),
+            Screen::Mode5(mode5) => Plot::<u8>::height(mode5),
+        }
+    }
+    fn plot(&mut self, x: i32, y: i32, color: u8) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.plot(x, y, color),
+            Screen::Mode3(mode3) => mode3.plot(x, y, color),
+            Screen::Mode5(mode5) => mode5.plot(x, y, color),
+        }
+    }
+}
+
+impl Draw for Screen<'_> {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.draw_dot(p, color),
+            Screen::Mode3(mode3) => mode3.draw_dot(p, color),
+            Screen::Mode5(mode5) => mode5.draw_dot(p, color),
+        }
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.draw_line(a, b, color),
+            Screen::Mode3(mode3) => mode3.draw_line(a, b, color),
+            Screen::Mode5(mode5) => mode5.draw_line(a, b, color),
+        }
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.draw_tri(tri, color),
+            Screen::Mode3(mode3) => mode3.draw_tri(tri, color),
+            Screen::Mode5(mode5) => mode5.draw_tri(tri, color),
+        }
+    }
+    fn draw_tri_rgb(
+        &mut self,
+        tri: &Triangle2D<DefaultNum>,
+        z: [DefaultNum; 3],
+        color: Rgb15,
+        index: u8,
+    ) {
+        match self {
+            Screen::Mode4(bitmap) => bitmap.draw_tri_rgb(tri, z, color, index),
+            Screen::Mode3(mode3) => mode3.draw_tri_rgb(tri, z, color, index),
+            Screen::Mode5(mode5) => mode5.draw_tri_rgb(tri, z, color, index),
+        }
+    }
+}
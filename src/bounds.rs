@@ -0,0 +1,146 @@
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vector3D<DefaultNum>,
+    pub max: Vector3D<DefaultNum>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere {
+    pub center: Vector3D<DefaultNum>,
+    pub radius: DefaultNum,
+}
+
+impl Aabb {
+    pub fn from_points(mut points: impl Iterator<Item = Vector3D<DefaultNum>>) -> Aabb {
+        let first = match points.next() {
+            Some(v) => v,
+            None => return Aabb::default(),
+        };
+        points.fold(
+            Aabb {
+                min: first,
+                max: first,
+            },
+            |aabb, v| Aabb {
+                min: Vector3D::new(
+                    aabb.min.x.min(v.x),
+                    aabb.min.y.min(v.y),
+                    aabb.min.z.min(v.z),
+                ),
+                max: Vector3D::new(
+                    aabb.max.x.max(v.x),
+                    aabb.max.y.max(v.y),
+                    aabb.max.z.max(v.z),
+                ),
+            },
+        )
+    }
+
+    pub fn center(&self) -> Vector3D<DefaultNum> {
+        (self.min + self.max) * num!(0.5)
+    }
+
+    pub fn half_extents(&self) -> Vector3D<DefaultNum> {
+        (self.max - self.min) * num!(0.5)
+    }
+
+    // the box stays axis aligned, so a rotated box grows to fit the rotated corners
+    pub fn transformed(&self, model: Matrix, pos: Vector3D<DefaultNum>) -> Aabb {
+        let center = model.transform(self.center()) + pos;
+        let h = self.half_extents();
+        let e = model.elem;
+        let extent = Vector3D::new(
+            e[0][0].abs() * h.x + e[0][1].abs() * h.y + e[0][2].abs() * h.z,
+            e[1][0].abs() * h.x + e[1][1].abs() * h.y + e[1][2].abs() * h.z,
+            e[2][0].abs() * h.x + e[2][1].abs() * h.y + e[2][2].abs() * h.z,
+        );
+        Aabb {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    pub fn contains(&self, point: Vector3D<DefaultNum>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    pub fn closest_point(&self, point: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        Vector3D::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+            point.z.clamp(self.min.z, self.max.z),
+        )
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Aabb {
+        let zero = Vector3D::new(num!(0.0), num!(0.0), num!(0.0));
+        Aabb {
+            min: zero,
+            max: zero,
+        }
+    }
+}
+
+impl BoundingSphere {
+    // centered on the box rather than a true minimal sphere, but it's cheap and close enough for a cull test
+    pub fn from_points(
+        points: impl Iterator<Item = Vector3D<DefaultNum>>,
+        aabb: &Aabb,
+    ) -> BoundingSphere {
+        let center = aabb.center();
+        let radius = points
+            .map(|v| (v - center).length())
+            .max()
+            .unwrap_or(num!(0.0));
+        BoundingSphere { center, radius }
+    }
+
+    pub fn transformed(&self, model: Matrix, pos: Vector3D<DefaultNum>) -> BoundingSphere {
+        // grow by the biggest scale, which is the longest column of the model matrix
+        let e = model.elem;
+        let stretch = (0..3)
+            .map(|c| Vector3D::new(e[0][c], e[1][c], e[2][c]).length())
+            .max()
+            .unwrap_or(num!(1.0));
+        BoundingSphere {
+            center: model.transform(self.center) + pos,
+            radius: self.radius * stretch,
+        }
+    }
+
+    pub fn overlaps(&self, other: &BoundingSphere) -> bool {
+        within(self.center, other.center, self.radius + other.radius)
+    }
+
+    pub fn overlaps_aabb(&self, aabb: &Aabb) -> bool {
+        within(self.center, aabb.closest_point(self.center), self.radius)
+    }
+}
+
+fn within(a: Vector3D<DefaultNum>, b: Vector3D<DefaultNum>, distance: DefaultNum) -> bool {
+    let d = a - b;
+    // squared on the raw values in 64 bits like length, a DefaultNum overflows once things are a few hundred apart
+    let square = |n: DefaultNum| (n.to_raw() as i64 * n.to_raw() as i64) as u64;
+    square(d.x) + square(d.y) + square(d.z) <= square(distance)
+}
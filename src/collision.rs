@@ -0,0 +1,254 @@
+/*
+mesh against mesh collision. the bounding spheres and boxes throw out anything that can't be touching, then
+every pair of triangles that's still close gets a separating axis test. only the surfaces are checked, so a
+mesh sitting entirely inside another one isn't a hit. morph targets and skins are ignored, it's the rest pose
+that collides.
+*/
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use agb::fixnum::num;
+
+use crate::bounds::Aabb;
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    // world space, roughly halfway between the two surfaces
+    pub point: Vector3D<DefaultNum>,
+    // unit length, pointing from the first mesh towards the second
+    pub normal: Vector3D<DefaultNum>,
+    // how far they'd have to move apart along the normal to stop touching
+    pub depth: DefaultNum,
+}
+
+struct WorldTriangle {
+    verticies: Triangle3D<DefaultNum>,
+    normal: Vector3D<DefaultNum>,
+    aabb: Aabb,
+}
+
+fn world_triangles(mesh: &Mesh, within: &Aabb) -> Vec<WorldTriangle> {
+    let model = mesh.model_matrix();
+    mesh.verticies
+        .iter()
+        .filter_map(|poly| {
+            let verticies = poly.verticies.map(|v| model.transform(v) + mesh.pos);
+            let aabb = Aabb::from_points(verticies.iter().copied());
+            if !aabb.overlaps(within) {
+                return None;
+            }
+            Some(WorldTriangle {
+                verticies,
+                normal: face_normal(&verticies),
+                aabb,
+            })
+        })
+        .collect()
+}
+
+// every point where the two meshes' triangles cross, empty if they aren't touching
+pub fn collide(a: &Mesh, b: &Mesh) -> Vec<Contact> {
+    let mut contacts = Vec::new();
+    if !a.world_sphere().overlaps(&b.world_sphere()) {
+        return contacts;
+    }
+    let a_box = a.world_aabb();
+    let b_box = b.world_aabb();
+    if !a_box.overlaps(&b_box) {
+        return contacts;
+    }
+
+    // triangles outside the other mesh's box can't touch anything
+    let a_tris = world_triangles(a, &b_box);
+    let b_tris = world_triangles(b, &a_box);
+    for ta in a_tris.iter() {
+        for tb in b_tris.iter().filter(|tb| tb.aabb.overlaps(&ta.aabb)) {
+            if let Some(contact) = triangle_contact(ta, tb) {
+                contacts.push(contact);
+            }
+        }
+    }
+    contacts
+}
+
+// the one to push apart by if there's only room to handle one
+pub fn deepest(contacts: &[Contact]) -> Option<Contact> {
+    contacts.iter().copied().max_by_key(|contact| contact.depth)
+}
+
+fn project(tri: &Triangle3D<DefaultNum>, axis: Vector3D<DefaultNum>) -> (DefaultNum, DefaultNum) {
+    let d = [axis.dot(tri[0]), axis.dot(tri[1]), axis.dot(tri[2])];
+    (d[0].min(d[1]).min(d[2]), d[0].max(d[1]).max(d[2]))
+}
+
+// the vertex furthest along the axis, averaged with any that are level with it
+fn support(tri: &Triangle3D<DefaultNum>, axis: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+    let (_, max) = project(tri, axis);
+    let mut total = Vector3D::new(num!(0.0), num!(0.0), num!(0.0));
+    let mut count = 0;
+    for v in tri.iter().filter(|v| axis.dot(**v) == max) {
+        total = total + *v;
+        count += 1;
+    }
+    total * (num!(1.0) / count)
+}
+
+// unit length, crossing the raw edges of big triangles overflows
+fn edge(tri: &WorldTriangle, i: usize) -> Vector3D<DefaultNum> {
+    (tri.verticies[(i + 1) % 3] - tri.verticies[i]).normalise()
+}
+
+fn coplanar(a: &WorldTriangle, b: &WorldTriangle) -> bool {
+    let across = a.normal.cross(b.normal);
+    across.x.abs() + across.y.abs() + across.z.abs() <= num!(0.01)
+        && a.normal.dot(b.verticies[0] - a.verticies[0]).abs() <= num!(0.0625)
+}
+
+// flat against each other every axis of the 3d test lies along the shared normal, which can't tell overlapping
+// from side by side. the edges' normals within the plane can, the same as a 2d triangle test
+fn coplanar_contact(a: &WorldTriangle, b: &WorldTriangle) -> Option<Contact> {
+    for tri in [a, b] {
+        for i in 0..3 {
+            let axis = tri.normal.cross(edge(tri, i));
+            let (a_min, a_max) = project(&a.verticies, axis);
+            let (b_min, b_max) = project(&b.verticies, axis);
+            if a_max < b_min || b_max < a_min {
+                return None;
+            }
+        }
+    }
+    // there's nothing to push apart along, they're touching flat on
+    let center = |tri: &Triangle3D<DefaultNum>| (tri[0] + tri[1] + tri[2]) * (num!(1.0) / 3);
+    Some(Contact {
+        point: (center(&a.verticies) + center(&b.verticies)) * num!(0.5),
+        normal: a.normal,
+        depth: num!(0.0),
+    })
+}
+
+// separating axis test, the axis with the least overlap is the contact normal. triangles that only just touch
+// are a contact with no depth
+fn triangle_contact(a: &WorldTriangle, b: &WorldTriangle) -> Option<Contact> {
+    if coplanar(a, b) {
+        return coplanar_contact(a, b);
+    }
+    let mut axes: Vec<Vector3D<DefaultNum>> = Vec::with_capacity(11);
+    axes.push(a.normal);
+    axes.push(b.normal);
+    for i in 0..3 {
+        let ea = edge(a, i);
+        for j in 0..3 {
+            let eb = edge(b, j);
+            // parallel edges don't give an axis, the face normals cover that case
+            let axis = ea.cross(eb);
+            if axis.x.abs() + axis.y.abs() + axis.z.abs() > num!(0.01) {
+                axes.push(axis.normalise());
+            }
+        }
+    }
+
+    let mut best: Option<(DefaultNum, Vector3D<DefaultNum>)> = None;
+    for axis in axes {
+        let (a_min, a_max) = project(&a.verticies, axis);
+        let (b_min, b_max) = project(&b.verticies, axis);
+        let forward = a_max - b_min;
+        let backward = b_max - a_min;
+        if forward < num!(0.0) || backward < num!(0.0) {
+            return None;
+        }
+        let (depth, normal) = if forward < backward {
+            (forward, axis)
+        } else {
+            (backward, -axis)
+        };
+        match best {
+            Some((best_depth, _)) if best_depth <= depth => {}
+            _ => best = Some((depth, normal)),
+        }
+    }
+
+    let (depth, normal) = best?;
+    let point = (support(&a.verticies, normal) + support(&b.verticies, -normal)) * num!(0.5);
+    Some(Contact {
+        point,
+        normal,
+        depth,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube_at(x: i32, y: i32, z: i32) -> Mesh {
+        let mut mesh = cube();
+        mesh.pos = Vector3D::new(DefaultNum::new(x), DefaultNum::new(y), DefaultNum::new(z));
+        mesh
+    }
+
+    fn triangle(verticies: [[i32; 3]; 3]) -> WorldTriangle {
+        let verticies = verticies.map(|[x, y, z]| {
+            Vector3D::new(DefaultNum::new(x), DefaultNum::new(y), DefaultNum::new(z))
+        });
+        WorldTriangle {
+            verticies,
+            normal: face_normal(&verticies),
+            aabb: Aabb::from_points(verticies.iter().copied()),
+        }
+    }
+
+    #[test_case]
+    fn separated(_gba: &mut agb::Gba) {
+        assert!(collide(&cube_at(0, 0, 0), &cube_at(100, 0, 0)).is_empty());
+    }
+
+    #[test_case]
+    fn touching(_gba: &mut agb::Gba) {
+        // the cubes are 48 across, so their sides meet flat at x = 24
+        let contacts = collide(&cube_at(0, 0, 0), &cube_at(48, 0, 0));
+        assert!(!contacts.is_empty());
+        assert!(contacts.iter().all(|contact| contact.depth < num!(0.1)));
+        assert!(contacts
+            .iter()
+            .any(|contact| contact.normal == Vector3D::new(num!(1.0), num!(0.0), num!(0.0))));
+    }
+
+    #[test_case]
+    fn overlapping(_gba: &mut agb::Gba) {
+        // off on every axis, so the sides aren't all flush with each other
+        let contacts = collide(&cube_at(0, 0, 0), &cube_at(40, 10, 10));
+        let contact = deepest(&contacts).unwrap();
+        assert!(contact.depth > num!(0.0));
+        let length = contact.normal.length();
+        assert!(length > num!(0.95) && length < num!(1.05));
+    }
+
+    #[test_case]
+    fn coplanar_triangles(_gba: &mut agb::Gba) {
+        let a = triangle([[0, 0, 0], [20, 0, 0], [0, 20, 0]]);
+        let overlapping = triangle([[5, 5, 0], [25, 5, 0], [5, 25, 0]]);
+        let beside = triangle([[30, 0, 0], [50, 0, 0], [30, 20, 0]]);
+        let contact = triangle_contact(&a, &overlapping).unwrap();
+        assert_eq!(contact.depth, num!(0.0));
+        assert_eq!(contact.normal, a.normal);
+        assert!(triangle_contact(&a, &beside).is_none());
+    }
+
+    #[test_case]
+    fn long_edges(_gba: &mut agb::Gba) {
+        // edges this long overflow a DefaultNum if they're crossed without scaling them down
+        let a = triangle([[-1000, -1000, 0], [1000, -1000, 0], [0, 1000, 0]]);
+        let b = triangle([[0, -500, -1000], [0, -500, 1000], [0, 500, 0]]);
+        let contact = triangle_contact(&a, &b).unwrap();
+        let length = contact.normal.length();
+        assert!(length > num!(0.95) && length < num!(1.05));
+        assert!(triangle_contact(
+            &a,
+            &triangle([[0, -500, 10], [0, -500, 1000], [0, 500, 500]])
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,430 @@
+/*
+a per pixel depth buffer, so meshes that aren't convex and objects that overlap come out right whatever order
+they're drawn in. wrap a target in Depth to use it:
+
+    depth.clear();
+    let mut target = Depth::new(&mut vram, &mut depth);
+    renderer.draw_mesh(&mut target, &camera, &mesh);
+
+clear it once per frame before the first mesh, every mesh drawn after that is tested against all the others.
+it keeps one u16 per pixel, 75KB at 240x160 and 40KB at 160x128, and the clear alone writes all of that. every
+covered pixel costs a read and a compare on top of the plot. give it a timer with with_timer and report prints
+how many cycles the clear and the tested drawing took each frame, tested and passed count the pixels.
+lines and dots are tested too, with keys going straight along the line between its ends, but don't write keys of
+their own. a line is too thin to hide anything, and it sits a little in front of where it is so an outline isn't
+lost under the fill it's drawn round. materials with an rgb are tested like everything else but come out in
+their ramp color, the depth buffer only plots palette colors.
+*/
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use agb::fixnum::Vector2D;
+use agb::timer::{Divider, Timer};
+
+use crate::drawing::*;
+use crate::geometry::*;
+use crate::matrix::*;
+
+pub struct DepthBuffer {
+    pub width: usize,
+    pub height: usize,
+    // one key per pixel row by row, bigger is closer and 0 is nothing drawn
+    keys: Vec<u16>,
+    // pixels looked at and pixels drawn since the last clear
+    pub tested: u32,
+    pub passed: u32,
+    // cycles the last clear took and the drawing since, to the nearest 64. both stay 0 without a timer
+    pub clear_cycles: u32,
+    pub draw_cycles: u32,
+    timer: Option<Timer>,
+    // one per row, for the same spans drawing::tri fills
+    bounds: Vec<usize>,
+}
+
+impl DepthBuffer {
+    pub fn new(width: usize, height: usize) -> DepthBuffer {
+        DepthBuffer {
+            width,
+            height,
+            keys: vec![0; width * height],
+            tested: 0,
+            passed: 0,
+            clear_cycles: 0,
+            draw_cycles: 0,
+            timer: None,
+            bounds: vec![0; height],
+        }
+    }
+
+    // a frame is 280896 cycles, at 64 a tick the timer only wraps after about 15 of them
+    pub fn with_timer(mut self, mut timer: Timer) -> DepthBuffer {
+        timer.set_divider(Divider::Divider64);
+        timer.set_enabled(true);
+        self.timer = Some(timer);
+        self
+    }
+
+    fn ticks(&self) -> u16 {
+        self.timer.as_ref().map_or(0, Timer::value)
+    }
+
+    fn cycles_since(&self, start: u16) -> u32 {
+        self.ticks().wrapping_sub(start) as u32 * 64
+    }
+
+    pub fn clear(&mut self) {
+        let start = self.ticks();
+        self.keys.fill(0);
+        self.tested = 0;
+        self.passed = 0;
+        self.draw_cycles = 0;
+        self.clear_cycles = self.cycles_since(start);
+    }
+
+    // memory the keys take up
+    pub fn bytes(&self) -> usize {
+        self.keys.len() * core::mem::size_of::<u16>()
+    }
+
+    pub fn report(&self) {
+        agb::println!(
+            "depth buffer: {} bytes, {} pixels tested, {} drawn, {} cycles clearing, {} drawing",
+            self.bytes(),
+            self.tested,
+            self.passed,
+            self.clear_cycles,
+            self.draw_cycles
+        );
+    }
+
+    // 1/z goes across the screen in a straight line where z doesn't, so that's what gets stored. anything
+    // nearer than 8 maxes out, the renderer's near plane drops it before it gets here anyway
+    pub fn key(z: DefaultNum) -> u16 {
+        if z.to_raw() <= 0 {
+            return u16::MAX;
+        }
+        ((1u64 << 31) / z.to_raw() as u64).min(u16::MAX as u64) as u16
+    }
+
+    // writes key and says yes if it's closer than what's already there
+    fn test(&mut self, x: i32, y: i32, key: u16) -> bool {
+        self.tested += 1;
+        let slot = &mut self.keys[y as usize * self.width + x as usize];
+        if key > *slot {
+            *slot = key;
+            self.passed += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // says yes if key is about as close as what's already there or closer, without writing it. lines and dots
+    // are moved 1/32 nearer first, so an outline beats the fill right under it but not anything in front
+    fn test_over(&mut self, x: i32, y: i32, key: u16) -> bool {
+        self.tested += 1;
+        let slot = self.keys[y as usize * self.width + x as usize] as u32;
+        let key = key as u32;
+        if key + (key >> 5) >= slot {
+            self.passed += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// a target drawn through a depth buffer. both have to be the same size
+pub struct Depth<'a, P: Plot + Draw> {
+    pub target: &'a mut P,
+    pub buffer: &'a mut DepthBuffer,
+}
+
+impl<'a, P: Plot + Draw> Depth<'a, P> {
+    pub fn new(target: &'a mut P, buffer: &'a mut DepthBuffer) -> Depth<'a, P> {
+        Depth { target, buffer }
+    }
+}
+
+impl<P: Plot + Draw> Draw for Depth<'_, P> {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        self.target.draw_dot(p, color);
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        self.target.draw_line(a, b, color);
+    }
+    fn draw_dot_depth(&mut self, p: Vector2D<DefaultNum>, z: DefaultNum, color: u8) {
+        depth_dot(self.target, self.buffer, p, z, color);
+    }
+    fn draw_line_depth(
+        &mut self,
+        a: Vector2D<DefaultNum>,
+        b: Vector2D<DefaultNum>,
+        z: [DefaultNum; 2],
+        color: u8,
+    ) {
+        depth_line(self.target, self.buffer, a, b, z, color);
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        self.target.draw_tri(tri, color);
+    }
+    fn draw_tri_depth(&mut self, tri: &Triangle2D<DefaultNum>, z: [DefaultNum; 3], color: u8) {
+        depth_tri(self.target, self.buffer, tri, z, color);
+    }
+}
+
+// covers the same spans as drawing::tri so fills line up with outlines drawn over them. keys go across the
+// screen in a straight line, so they're a flat plane through the corners that each span steps along
+pub fn depth_tri<P: Plot>(
+    target: &mut P,
+    buffer: &mut DepthBuffer,
+    tri: &Triangle2D<DefaultNum>,
+    z: [DefaultNum; 3],
+    color: u8,
+) {
+    let began = buffer.ticks();
+    let width = target.width().min(buffer.width as i32);
+    let height = target.height().min(buffer.height as i32);
+
+    // the corners drawing::tri works from, keys are 16.16 from here on
+    let corners = [tri[0].trunc(), tri[1].trunc(), tri[2].trunc()];
+    let keys = z.map(|z| DepthBuffer::key(z) as i64);
+    let (ax, ay) = (
+        (corners[1].x - corners[0].x) as i64,
+        (corners[1].y - corners[0].y) as i64,
+    );
+    let (bx, by) = (
+        (corners[2].x - corners[0].x) as i64,
+        (corners[2].y - corners[0].y) as i64,
+    );
+    let area = ax * by - ay * bx;
+    let (base, step_x, step_y) = if area == 0 {
+        // edge on, there's no plane. the nearest corner covers the whole line
+        (keys[0].max(keys[1]).max(keys[2]) << 16, 0, 0)
+    } else {
+        let (ka, kb) = (keys[1] - keys[0], keys[2] - keys[0]);
+        (
+            keys[0] << 16,
+            ((ka * by - kb * ay) << 16) / area,
+            ((kb * ax - ka * bx) << 16) / area,
+        )
+    };
+    let key_at = |x: i32, y: i32| {
+        base + step_x * (x - corners[0].x) as i64 + step_y * (y - corners[0].y) as i64
+    };
+
+    // taken out for the walk so the keys can be tested at the same time
+    let mut bounds = core::mem::take(&mut buffer.bounds);
+    spans(width, height, &mut bounds, tri, |y, start, end| {
+        // only the part of the span that's on screen gets walked
+        let clipped = start.max(0);
+        let mut key = key_at(clipped, y);
+        for x in clipped..=end.min(width - 1) {
+            // bresenham can step just outside the corners, where the plane carries on past them
+            let clamped = key.clamp(1 << 16, (u16::MAX as i64) << 16) >> 16;
+            if buffer.test(x, y, clamped as u16) {
+                target.plot(x, y, color);
+            }
+            key += step_x;
+        }
+    });
+    buffer.bounds = bounds;
+    buffer.draw_cycles += buffer.cycles_since(began);
+}
+
+// drawing::line and drawing::dot plot through this, so tested lines and dots cover exactly the pixels untested
+// ones do. keys are 16.16 and step along x and y from start like depth_tri's plane
+struct Tested<'a, P: Plot> {
+    target: &'a mut P,
+    buffer: &'a mut DepthBuffer,
+    start: Vector2D<i32>,
+    base: i64,
+    step_x: i64,
+    step_y: i64,
+}
+
+impl<P: Plot> Plot for Tested<'_, P> {
+    fn width(&self) -> i32 {
+        self.target.width().min(self.buffer.width as i32)
+    }
+    fn height(&self) -> i32 {
+        self.target.height().min(self.buffer.height as i32)
+    }
+    fn plot(&mut self, x: i32, y: i32, color: u8) {
+        let key = self.base
+            + self.step_x * (x - self.start.x) as i64
+            + self.step_y * (y - self.start.y) as i64;
+        let key = (key >> 16).clamp(1, u16::MAX as i64) as u16;
+        if self.buffer.test_over(x, y, key) {
+            self.target.plot(x, y, color);
+        }
+    }
+}
+
+// 1/z goes along a line on screen in a straight line too, so keys step evenly along whichever axis it's longer in
+pub fn depth_line<P: Plot>(
+    target: &mut P,
+    buffer: &mut DepthBuffer,
+    a: Vector2D<DefaultNum>,
+    b: Vector2D<DefaultNum>,
+    z: [DefaultNum; 2],
+    color: u8,
+) {
+    let began = buffer.ticks();
+    let (start, end) = (a.trunc(), b.trunc());
+    let keys = z.map(|z| DepthBuffer::key(z) as i64);
+    let (w, h) = (end.x - start.x, end.y - start.y);
+    let (base, step_x, step_y) = if w == 0 && h == 0 {
+        (keys[0].max(keys[1]) << 16, 0, 0)
+    } else if w.abs() >= h.abs() {
+        (keys[0] << 16, ((keys[1] - keys[0]) << 16) / w as i64, 0)
+    } else {
+        (keys[0] << 16, 0, ((keys[1] - keys[0]) << 16) / h as i64)
+    };
+    let mut tested = Tested {
+        target,
+        buffer: &mut *buffer,
+        start,
+        base,
+        step_x,
+        step_y,
+    };
+    line(&mut tested, a, b, color);
+    buffer.draw_cycles += buffer.cycles_since(began);
+}
+
+pub fn depth_dot<P: Plot>(
+    target: &mut P,
+    buffer: &mut DepthBuffer,
+    p: Vector2D<DefaultNum>,
+    z: DefaultNum,
+    color: u8,
+) {
+    let began = buffer.ticks();
+    let mut tested = Tested {
+        target,
+        buffer: &mut *buffer,
+        start: p.trunc(),
+        base: (DepthBuffer::key(z) as i64) << 16,
+        step_x: 0,
+        step_y: 0,
+    };
+    dot(&mut tested, p, color);
+    buffer.draw_cycles += buffer.cycles_since(began);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::Framebuffer;
+    use agb::fixnum::num;
+
+    fn p(x: i32, y: i32) -> Vector2D<DefaultNum> {
+        Vector2D::new(DefaultNum::new(x), DefaultNum::new(y))
+    }
+
+    #[test_case]
+    fn same_pixels_as_tri(_gba: &mut agb::Gba) {
+        let tris = [
+            [p(-4, -2), p(12, 3), p(2, 12)],
+            [p(1, 1), p(6, 2), p(3, 7)],
+            [p(7, 0), p(0, 3), p(5, 7)],
+            [p(2, 2), p(6, 2), p(4, 2)],
+        ];
+        for tri in tris.iter() {
+            let mut plain = Framebuffer::new(8, 8);
+            plain.draw_tri(tri, 1);
+            let mut tested = Framebuffer::new(8, 8);
+            let mut buffer = DepthBuffer::new(8, 8);
+            depth_tri(
+                &mut tested,
+                &mut buffer,
+                tri,
+                [num!(64.0), num!(32.0), num!(96.0)],
+                1,
+            );
+            assert_eq!(tested.pixels, plain.pixels);
+            // every pixel once, nothing was there to hide it
+            assert_eq!(buffer.passed, buffer.tested);
+        }
+    }
+
+    #[test_case]
+    fn nearer_wins(_gba: &mut agb::Gba) {
+        let everything = [p(-10, -10), p(30, -10), p(-10, 30)];
+        let near = (num!(32.0), 2);
+        let far = (num!(96.0), 1);
+        for order in [[near, far], [far, near]] {
+            let mut framebuffer = Framebuffer::new(8, 8);
+            let mut buffer = DepthBuffer::new(8, 8);
+            for (z, color) in order {
+                depth_tri(&mut framebuffer, &mut buffer, &everything, [z; 3], color);
+            }
+            assert!(framebuffer.pixels.iter().all(|&pixel| pixel == 2));
+        }
+    }
+
+    #[test_case]
+    fn same_pixels_as_line(_gba: &mut agb::Gba) {
+        let lines = [(p(0, 0), p(7, 3)), (p(6, -2), p(1, 9)), (p(3, 3), p(3, 3))];
+        for (a, b) in lines {
+            let mut plain = Framebuffer::new(8, 8);
+            plain.draw_line(a, b, 1);
+            let mut tested = Framebuffer::new(8, 8);
+            let mut buffer = DepthBuffer::new(8, 8);
+            depth_line(&mut tested, &mut buffer, a, b, [num!(32.0), num!(96.0)], 1);
+            assert_eq!(tested.pixels, plain.pixels);
+            // lines don't write keys, so nothing after them is hidden
+            assert!(buffer.keys.iter().all(|&key| key == 0));
+        }
+    }
+
+    #[test_case]
+    fn outlines_hidden_behind(_gba: &mut agb::Gba) {
+        let everything = [p(-10, -10), p(30, -10), p(-10, 30)];
+        let mut framebuffer = Framebuffer::new(8, 8);
+        let mut buffer = DepthBuffer::new(8, 8);
+        depth_tri(
+            &mut framebuffer,
+            &mut buffer,
+            &everything,
+            [num!(64.0); 3],
+            1,
+        );
+
+        // behind the fill, in front of it and right on it like an outline
+        depth_line(
+            &mut framebuffer,
+            &mut buffer,
+            p(0, 1),
+            p(7, 1),
+            [num!(96.0); 2],
+            2,
+        );
+        depth_line(
+            &mut framebuffer,
+            &mut buffer,
+            p(0, 3),
+            p(7, 3),
+            [num!(32.0); 2],
+            3,
+        );
+        depth_line(
+            &mut framebuffer,
+            &mut buffer,
+            p(0, 5),
+            p(7, 5),
+            [num!(64.0); 2],
+            4,
+        );
+        depth_dot(&mut framebuffer, &mut buffer, p(2, 7), num!(96.0), 5);
+        depth_dot(&mut framebuffer, &mut buffer, p(4, 7), num!(32.0), 6);
+        assert!(framebuffer.pixels[8..16].iter().all(|&pixel| pixel == 1));
+        assert!(framebuffer.pixels[24..31].iter().all(|&pixel| pixel == 3));
+        assert!(framebuffer.pixels[40..47].iter().all(|&pixel| pixel == 4));
+        assert_eq!(framebuffer.get(2, 7), 1);
+        assert_eq!(framebuffer.get(4, 7), 6);
+    }
+}
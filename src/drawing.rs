@@ -10,22 +10,207 @@ use agb::{
     fixnum::{num, FixedNum, Num, Vector2D},
 };
 
+use crate::backend::Rgb15;
 use crate::geometry::*;
 use crate::matrix::*;
 
 pub trait Draw {
     fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8);
     fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8);
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8);
+    // z is how far each corner is from the camera. only targets with a depth buffer care
+    fn draw_tri_depth(&mut self, tri: &Triangle2D<DefaultNum>, _z: [DefaultNum; 3], color: u8) {
+        self.draw_tri(tri, color);
+    }
+    // the same for each end of a line
+    fn draw_line_depth(
+        &mut self,
+        a: Vector2D<DefaultNum>,
+        b: Vector2D<DefaultNum>,
+        _z: [DefaultNum; 2],
+        color: u8,
+    ) {
+        self.draw_line(a, b, color);
+    }
+    fn draw_dot_depth(&mut self, p: Vector2D<DefaultNum>, _z: DefaultNum, color: u8) {
+        self.draw_dot(p, color);
+    }
+    // for targets that can show any color. the rest draw index, the material's nearest palette color
+    fn draw_tri_rgb(
+        &mut self,
+        tri: &Triangle2D<DefaultNum>,
+        z: [DefaultNum; 3],
+        _color: Rgb15,
+        index: u8,
+    ) {
+        self.draw_tri_depth(tri, z, index);
+    }
+    // how far away whatever gets drawn next is, for targets that sort what's drawn into them
+    fn depth_hint(&mut self, _z: [DefaultNum; 3]) {}
 }
 
-impl Draw for agb::display::bitmap4::Bitmap4<'_> {
-    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
-        let a = a.trunc();
-        let b = b.trunc();
-        let mut x = a.x;
-        let mut y = a.y;
-        let w = b.x - a.x;
-        let h = b.y - a.y;
+// the pixel level part of a target. line, tri and dot below only go through this, so every target draws
+// exactly the same pixels. C is whatever a pixel holds, palette indicies unless the target is direct color
+pub trait Plot<C: Copy = u8> {
+    fn width(&self) -> i32;
+    fn height(&self) -> i32;
+    // x and y are always on screen
+    fn plot(&mut self, x: i32, y: i32, color: C);
+    // x is always even, bitmap4 can write both pixels at once
+    fn plot_pair(&mut self, x: i32, y: i32, color: C) {
+        self.plot(x, y, color);
+        if x + 1 < self.width() {
+            self.plot(x + 1, y, color);
+        }
+    }
+}
+
+pub fn dot<C: Copy, P: Plot<C>>(target: &mut P, p: Vector2D<DefaultNum>, color: C) {
+    let p = p.trunc();
+    if (0..target.width()).contains(&p.x) && (0..target.height()).contains(&p.y) {
+        target.plot(p.x, p.y, color);
+    }
+}
+
+pub fn line<C: Copy, P: Plot<C>>(
+    target: &mut P,
+    a: Vector2D<DefaultNum>,
+    b: Vector2D<DefaultNum>,
+    color: C,
+) {
+    let a = a.trunc();
+    let b = b.trunc();
+    let mut x = a.x;
+    let mut y = a.y;
+    let w = b.x - a.x;
+    let h = b.y - a.y;
+    let dx1 = {
+        if w < 0 {
+            -1
+        } else if w > 0 {
+            1
+        } else {
+            0
+        }
+    };
+    let dy1 = {
+        if h < 0 {
+            -1
+        } else if h > 0 {
+            1
+        } else {
+            0
+        }
+    };
+    let mut dx2 = dx1;
+    let mut dy2 = 0;
+    let mut longest = w.abs();
+    let mut shortest = h.abs();
+    if longest <= shortest {
+        longest = h.abs();
+        shortest = w.abs();
+        if h < 0 {
+            dy2 = -1;
+        } else if h > 0 {
+            dy2 = 1;
+        }
+        dx2 = 0;
+    }
+    let mut numerator = longest >> 1;
+    for _ in 0..longest {
+        // lines from line meshes can run off the edge of the screen
+        if (0..target.width()).contains(&x) && (0..target.height()).contains(&y) {
+            target.plot(x, y, color);
+        }
+        numerator += shortest;
+        if numerator >= longest {
+            numerator -= longest;
+            x += dx1;
+            y += dy1;
+        } else {
+            x += dx2;
+            y += dy2;
+        }
+    }
+}
+
+// bounds needs a slot for every row of the target
+pub fn tri<C: Copy, P: Plot<C>>(
+    target: &mut P,
+    bounds: &mut [usize],
+    tri: &Triangle2D<DefaultNum>,
+    color: C,
+) {
+    let width = target.width();
+    let height = target.height();
+    spans(width, height, bounds, tri, |y, start, end| {
+        if (0..width).contains(&start) {
+            target.plot(start, y, color);
+        }
+        for x in start..end {
+            if x & 1 == 0 && (x >= 0 && x < width) {
+                target.plot_pair(x, y, color);
+            }
+        }
+        if (0..width).contains(&end) {
+            target.plot(end, y, color);
+        }
+    });
+}
+
+// the rows tri fills as (y, start, end) with start <= end, for anything else that has to cover exactly the same
+// pixels. y is always on screen, the ends can be off either side
+pub fn spans(
+    width: i32,
+    height: i32,
+    bounds: &mut [usize],
+    tri: &Triangle2D<DefaultNum>,
+    mut span: impl FnMut(i32, i32, i32),
+) {
+    // Uses a slightly modified version of the bresenham fill method
+    let tri = [tri[0].trunc(), tri[1].trunc(), tri[2].trunc()];
+
+    // find point that is inbetween the other verticies on the y-axis
+    let midpoint_index: usize = {
+        if tri[0].y > tri[1].y {
+            if tri[1].y > tri[2].y {
+                1
+            } else if tri[0].y > tri[2].y {
+                2
+            } else {
+                0
+            }
+        } else {
+            if tri[0].y > tri[2].y {
+                0
+            } else if tri[1].y > tri[2].y {
+                2
+            } else {
+                1
+            }
+        }
+    };
+
+    // this edge is the tallest
+    let outside_indicies = {
+        match midpoint_index {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        }
+    };
+
+    let edges = [
+        (tri[midpoint_index], tri[outside_indicies.0]),
+        (tri[midpoint_index], tri[outside_indicies.1]),
+        (tri[outside_indicies.0], tri[outside_indicies.1]),
+    ];
+
+    for edge in 0..=2 {
+        let mut x = edges[edge].0.x;
+        let mut y = edges[edge].0.y;
+        let w = edges[edge].1.x - edges[edge].0.x;
+        let h = edges[edge].1.y - edges[edge].0.y;
         let dx1 = {
             if w < 0 {
                 -1
@@ -59,154 +244,76 @@ impl Draw for agb::display::bitmap4::Bitmap4<'_> {
             dx2 = 0;
         }
         let mut numerator = longest >> 1;
-        for i in 0..longest {
-            self.draw_point(x as i32, y as i32, color);
-            numerator += shortest;
-            if numerator >= longest {
-                numerator -= longest;
-                x += dx1;
-                y += dy1;
-            } else {
-                x += dx2;
-                y += dy2;
-            }
-        }
-    }
-    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
-        // Uses a slightly modified version of the bresenham fill method
-        static mut X_BOUNDS: [usize; 160] = [0; 160];
-        let tri = [tri[0].trunc(), tri[1].trunc(), tri[2].trunc()];
 
-        // find point that is inbetween the other verticies on the y-axis
-        let midpoint_index: usize = {
-            if tri[0].y > tri[1].y {
-                if tri[1].y > tri[2].y {
-                    1
-                } else if tri[0].y > tri[2].y {
-                    2
-                } else {
-                    0
+        if edge < 2 {
+            // get all furthest points on the x-axis that the two shortest edges of the triangle reach.
+            for _ in 0..=longest {
+                if y >= 0 && y < height {
+                    bounds[y as usize] = x.clamp(0, width - 1) as usize;
                 }
-            } else {
-                if tri[0].y > tri[2].y {
-                    0
-                } else if tri[1].y > tri[2].y {
-                    2
+                numerator += shortest;
+                if numerator >= longest {
+                    numerator -= longest;
+                    x += dx1;
+                    y += dy1;
                 } else {
-                    1
+                    x += dx2;
+                    y += dy2;
                 }
             }
-        };
-
-        // this edge is the tallest
-        let outside_indicies = {
-            match midpoint_index {
-                0 => (1, 2),
-                1 => (0, 2),
-                _ => (0, 1),
-            }
-        };
-
-        let edges = [
-            (tri[midpoint_index], tri[outside_indicies.0]),
-            (tri[midpoint_index], tri[outside_indicies.1]),
-            (tri[outside_indicies.0], tri[outside_indicies.1]),
-        ];
-
-        for edge in 0..=2 {
-            let mut x = edges[edge].0.x;
-            let mut y = edges[edge].0.y;
-            let w = edges[edge].1.x - edges[edge].0.x;
-            let h = edges[edge].1.y - edges[edge].0.y;
-            let dx1 = {
-                if w < 0 {
-                    -1
-                } else if w > 0 {
-                    1
-                } else {
-                    0
-                }
-            };
-            let dy1 = {
-                if h < 0 {
-                    -1
-                } else if h > 0 {
-                    1
-                } else {
-                    0
-                }
-            };
-            let mut dx2 = dx1;
-            let mut dy2 = 0;
-            let mut longest = w.abs();
-            let mut shortest = h.abs();
-            if longest <= shortest {
-                longest = h.abs();
-                shortest = w.abs();
-                if h < 0 {
-                    dy2 = -1;
-                } else if h > 0 {
-                    dy2 = 1;
-                }
-                dx2 = 0;
-            }
-            let mut numerator = longest >> 1;
-
-            if edge < 2 {
-                // get all furthest points on the x-axis that the two shortest edges of the triangle reach.
-                for _ in 0..=longest {
-                    if y >= 0 && y <= 159 {
-                        unsafe {
-                            X_BOUNDS[y as usize] = x.clamp(0, 239) as usize;
-                        }
-                    }
-                    numerator += shortest;
-                    if numerator >= longest {
-                        numerator -= longest;
-                        x += dx1;
-                        y += dy1;
+        } else {
+            // if all the bounds are set, draw the triangle. this is the last step.
+            let mut prev_y: i32 = -1;
+            for _ in 0..longest {
+                // if the y-axis has changed, draw a horizontal line up to the furthest point on the x-axis provided by the two shortest edges.
+                if prev_y != y && (y >= 0 && y < height) {
+                    if x > bounds[y as usize] as i32 {
+                        span(y, bounds[y as usize] as i32, x);
                     } else {
-                        x += dx2;
-                        y += dy2;
+                        span(y, x, bounds[y as usize] as i32);
                     }
+                    prev_y = y;
                 }
-            } else {
-                // if all the bounds are set, draw the triangle. this is the last step.
-                let mut prev_y: i32 = -1;
-                for i in 0..longest {
-                    // if the y-axis has changed, draw a horizontal line up to the furthest point on the x-axis provided by the two shortest edges.
-                    if prev_y != y && (y >= 0 && y <= 159) {
-                        {
-                            let (start, end) = {
-                                unsafe {
-                                    if x > X_BOUNDS[y as usize] as i32 {
-                                        (X_BOUNDS[y as usize] as i32, x)
-                                    } else {
-                                        (x, X_BOUNDS[y as usize] as i32)
-                                    }
-                                }
-                            };
-                            self.draw_point(start, y, color);
-                            for x in start..end {
-                                if x & 1 == 0 && (x >= 0 && x <= 239) && (y >= 0 && y <= 159) {
-                                    self.draw_wide_point(x, y, color);
-                                }
-                            }
-                            self.draw_point(end, y, color);
-                        }
-                        prev_y = y;
-                    }
-                    numerator += shortest;
-                    if numerator >= longest {
-                        numerator -= longest;
-                        x += dx1;
-                        y += dy1;
-                    } else {
-                        x += dx2;
-                        y += dy2;
-                    }
+                numerator += shortest;
+                if numerator >= longest {
+                    numerator -= longest;
+                    x += dx1;
+                    y += dy1;
+                } else {
+                    x += dx2;
+                    y += dy2;
                 }
             }
         }
     }
 }
+
+impl Plot for agb::display::bitmap4::Bitmap4<'_> {
+    fn width(&self) -> i32 {
+        240
+    }
+    fn height(&self) -> i32 {
+        160
+    }
+    fn plot(&mut self, x: i32, y: i32, color: u8) {
+        self.draw_point(x, y, color);
+    }
+    fn plot_pair(&mut self, x: i32, y: i32, color: u8) {
+        self.draw_wide_point(x, y, color);
+    }
+}
+
+impl Draw for agb::display::bitmap4::Bitmap4<'_> {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        dot(self, p, color);
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        line(self, a, b, color);
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        static mut X_BOUNDS: [usize; 160] = [0; 160];
+        // only ever touched from here, and there's only one thread
+        let bounds = unsafe { &mut *core::ptr::addr_of_mut!(X_BOUNDS) };
+        self::tri(self, bounds, tri, color);
+    }
+}
@@ -0,0 +1,238 @@
+/*
+an 8 bit image in memory that can be drawn to like the screen. good for drawing offscreen, rendering into a
+texture, or checking exactly which pixels something draws without the hardware.
+*/
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use agb::fixnum::Vector2D;
+
+use crate::drawing::*;
+use crate::geometry::*;
+use crate::matrix::*;
+use crate::texture::Texture;
+
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    // palette indicies, row by row
+    pub pixels: Vec<u8>,
+    // one per row, for filling triangles
+    bounds: Vec<usize>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height],
+            bounds: vec![0; height],
+        }
+    }
+
+    pub fn clear(&mut self, color: u8) {
+        self.pixels.fill(color);
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    // copies the whole image onto another target with its top left corner at x, y. anything off the edge is cut
+    pub fn blit<P: Plot>(&self, target: &mut P, x: i32, y: i32) {
+        for row in 0..self.height as i32 {
+            let ty = y + row;
+            if !(0..target.height()).contains(&ty) {
+                continue;
+            }
+            for column in 0..self.width as i32 {
+                let tx = x + column;
+                if (0..target.width()).contains(&tx) {
+                    target.plot(tx, ty, self.get(column as usize, row as usize));
+                }
+            }
+        }
+    }
+
+    // a copy of what's been drawn, the size has to be a power of two each way
+    pub fn to_texture(&self) -> Texture {
+        Texture::new(self.width, self.height, self.pixels.clone(), Vec::new())
+    }
+}
+
+impl Plot for Framebuffer {
+    fn width(&self) -> i32 {
+        self.width as i32
+    }
+    fn height(&self) -> i32 {
+        self.height as i32
+    }
+    fn plot(&mut self, x: i32, y: i32, color: u8) {
+        self.pixels[y as usize * self.width + x as usize] = color;
+    }
+}
+
+impl Draw for Framebuffer {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        dot(self, p, color);
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        line(self, a, b, color);
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        // taken out for the draw so the pixels can be borrowed at the same time
+        let mut bounds = core::mem::take(&mut self.bounds);
+        crate::drawing::tri(self, &mut bounds, tri, color);
+        self.bounds = bounds;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn p(x: i32, y: i32) -> Vector2D<DefaultNum> {
+        Vector2D::new(DefaultNum::new(x), DefaultNum::new(y))
+    }
+
+    // # is anything drawn, . is still clear
+    fn assert_image(framebuffer: &Framebuffer, expected: &[&str]) {
+        assert_eq!(expected.len(), framebuffer.height);
+        for (y, row) in expected.iter().enumerate() {
+            assert_eq!(row.len(), framebuffer.width);
+            for (x, c) in row.bytes().enumerate() {
+                assert_eq!(framebuffer.get(x, y) != 0, c == b'#', "pixel {}, {}", x, y);
+            }
+        }
+    }
+
+    #[test_case]
+    fn line(_gba: &mut agb::Gba) {
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.draw_line(p(1, 1), p(6, 4), 1);
+        // the end point itself is left off
+        #[rustfmt::skip]
+        let expected = [
+            "........",
+            ".#......",
+            "..##....",
+            "....##..",
+            "........",
+            "........",
+            "........",
+            "........",
+        ];
+        assert_image(&framebuffer, &expected);
+    }
+
+    #[test_case]
+    fn line_off_the_edges(_gba: &mut agb::Gba) {
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.draw_line(p(-3, 6), p(10, 2), 1);
+        #[rustfmt::skip]
+        let expected = [
+            "........",
+            "........",
+            "........",
+            "......##",
+            "..####..",
+            "##......",
+            "........",
+            "........",
+        ];
+        assert_image(&framebuffer, &expected);
+    }
+
+    #[test_case]
+    fn tri_clipped(_gba: &mut agb::Gba) {
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.draw_tri(&[p(-4, -2), p(12, 3), p(2, 12)], 1);
+        #[rustfmt::skip]
+        let expected = [
+            "##......",
+            "######..",
+            "########",
+            "########",
+            "########",
+            "########",
+            "########",
+            "########",
+        ];
+        assert_image(&framebuffer, &expected);
+    }
+
+    #[test_case]
+    fn odd_and_even_spans(_gba: &mut agb::Gba) {
+        // spans starting on an odd x get their first pixel on its own before the pairs
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.draw_tri(&[p(3, 1), p(6, 1), p(3, 6)], 1);
+        #[rustfmt::skip]
+        let expected = [
+            "........",
+            "...####.",
+            "...###..",
+            "...###..",
+            "...##...",
+            "...##...",
+            "........",
+            "........",
+        ];
+        assert_image(&framebuffer, &expected);
+
+        let mut framebuffer = Framebuffer::new(8, 8);
+        framebuffer.draw_tri(&[p(2, 1), p(5, 1), p(2, 6)], 1);
+        #[rustfmt::skip]
+        let expected = [
+            "........",
+            "..####..",
+            "..###...",
+            "..###...",
+            "..##....",
+            "..##....",
+            "........",
+            "........",
+        ];
+        assert_image(&framebuffer, &expected);
+    }
+
+    // writes both pixels of a pair at once with no checks, the way Bitmap4 does
+    struct Wide(Framebuffer);
+
+    impl Plot for Wide {
+        fn width(&self) -> i32 {
+            self.0.width()
+        }
+        fn height(&self) -> i32 {
+            self.0.height()
+        }
+        fn plot(&mut self, x: i32, y: i32, color: u8) {
+            self.0.plot(x, y, color);
+        }
+        fn plot_pair(&mut self, x: i32, y: i32, color: u8) {
+            let i = y as usize * self.0.width + x as usize;
+            self.0.pixels[i..i + 2].fill(color);
+        }
+    }
+
+    #[test_case]
+    fn same_pixels_as_paired_writes(_gba: &mut agb::Gba) {
+        let tris = [
+            [p(3, 1), p(6, 1), p(3, 6)],
+            [p(-4, -2), p(12, 3), p(2, 12)],
+            [p(7, 0), p(0, 5), p(5, 7)],
+            [p(1, 6), p(6, 2), p(9, 7)],
+        ];
+        for tri in tris {
+            let mut framebuffer = Framebuffer::new(8, 8);
+            framebuffer.draw_tri(&tri, 1);
+            let mut wide = Wide(Framebuffer::new(8, 8));
+            let mut bounds = vec![0; 8];
+            crate::drawing::tri(&mut wide, &mut bounds, &tri, 1);
+            assert_eq!(framebuffer.pixels, wide.0.pixels);
+        }
+    }
+}
@@ -1,12 +1,20 @@
 use agb::fixnum::{num, FixedNum, Number, Vector2D};
 extern crate alloc;
+use alloc::borrow::Cow;
 use alloc::vec;
 use alloc::vec::Vec;
+use core::ops::{Add, Mul, Neg, Sub};
 
-use crate::matrix::DefaultNum;
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::lod::Lod;
+use crate::material::{Material, MISSING};
+use crate::matrix::{self, DefaultNum, Matrix, MatrixMath};
+use crate::morph::MorphTarget;
+use crate::physics::RigidBody;
+use crate::skeleton::Skin;
 
 const ZERO: DefaultNum = agb::fixnum::Num::from_raw(0);
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Vector3D<T> {
     pub x: T,
     pub y: T,
@@ -14,297 +22,506 @@ pub struct Vector3D<T> {
 }
 pub type Triangle2D<T> = [Vector2D<T>; 3];
 pub type Triangle3D<T> = [Vector3D<T>; 3];
+
+impl<T> Vector3D<T> {
+    pub const fn new(x: T, y: T, z: T) -> Vector3D<T> {
+        Vector3D { x, y, z }
+    }
+}
+
+impl Vector3D<DefaultNum> {
+    pub fn dot(self, rhs: Vector3D<DefaultNum>) -> DefaultNum {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn cross(self, rhs: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        Vector3D {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        }
+    }
+
+    pub fn length(self) -> DefaultNum {
+        // squared on the raw values in 64 bits, anything much over 700 units long overflows a DefaultNum when squared
+        let square = |n: DefaultNum| (n.to_raw() as i64 * n.to_raw() as i64) as u64;
+        let raw = matrix::isqrt(square(self.x) + square(self.y) + square(self.z));
+        DefaultNum::from_raw(raw as i32)
+    }
+
+    pub fn normalise(self) -> Vector3D<DefaultNum> {
+        // shrink the vector so its largest component is 1 first, otherwise squaring it overflows
+        let largest = self.x.abs().max(self.y.abs()).max(self.z.abs());
+        if largest == ZERO {
+            return self;
+        }
+        let scaled = Vector3D {
+            x: matrix::div(self.x, largest),
+            y: matrix::div(self.y, largest),
+            z: matrix::div(self.z, largest),
+        };
+        let length = scaled.length();
+        Vector3D {
+            x: matrix::div(scaled.x, length),
+            y: matrix::div(scaled.y, length),
+            z: matrix::div(scaled.z, length),
+        }
+    }
+}
+
+impl Add for Vector3D<DefaultNum> {
+    type Output = Vector3D<DefaultNum>;
+    fn add(self, rhs: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        Vector3D {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for Vector3D<DefaultNum> {
+    type Output = Vector3D<DefaultNum>;
+    fn sub(self, rhs: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        Vector3D {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl Mul<DefaultNum> for Vector3D<DefaultNum> {
+    type Output = Vector3D<DefaultNum>;
+    fn mul(self, rhs: DefaultNum) -> Vector3D<DefaultNum> {
+        Vector3D {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl Neg for Vector3D<DefaultNum> {
+    type Output = Vector3D<DefaultNum>;
+    fn neg(self) -> Vector3D<DefaultNum> {
+        Vector3D {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+// which side of a polygon gets thrown away before it is transformed, each material picks one
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CullMode {
+    Back,
+    Front,
+    None,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct Polygon {
-    pub color: u8,
+    // index into the mesh's materials
+    pub material: u8,
     pub verticies: Triangle3D<DefaultNum>,
+    // unit length, points out of the front face
+    pub normal: Vector3D<DefaultNum>,
+    // texture coordinates for each vertex, 0 to 1 covers the texture once and anything outside wraps
+    pub uv: [Vector2D<DefaultNum>; 3],
+}
+
+const NO_UV: [Vector2D<DefaultNum>; 3] = [Vector2D { x: ZERO, y: ZERO }; 3];
+
+impl Polygon {
+    // for polygons declared in const or static data, where the normal can't be worked out for us
+    pub const fn from_parts(
+        material: u8,
+        verticies: Triangle3D<DefaultNum>,
+        normal: Vector3D<DefaultNum>,
+    ) -> Polygon {
+        Polygon {
+            material,
+            verticies,
+            normal,
+            uv: NO_UV,
+        }
+    }
+
+    pub fn new(material: u8, verticies: Triangle3D<DefaultNum>) -> Polygon {
+        Polygon {
+            material,
+            verticies,
+            normal: face_normal(&verticies),
+            uv: NO_UV,
+        }
+    }
+
+    pub const fn with_uv(self, uv: [Vector2D<DefaultNum>; 3]) -> Polygon {
+        Polygon { uv, ..self }
+    }
+
+    // eye is the camera position in the same object space as the verticies, cull comes from the material
+    pub fn is_visible(&self, eye: Vector3D<DefaultNum>, cull: CullMode) -> bool {
+        let facing = self.normal.dot(eye - self.verticies[0]);
+        match cull {
+            CullMode::Back => facing > ZERO,
+            CullMode::Front => facing < ZERO,
+            CullMode::None => true,
+        }
+    }
+}
+
+pub fn face_normal(tri: &Triangle3D<DefaultNum>) -> Vector3D<DefaultNum> {
+    let a = tri[1] - tri[0];
+    let b = tri[2] - tri[0];
+    // scale the edges down before crossing them so big polygons don't overflow
+    a.normalise().cross(b.normalise()).normalise()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Line {
+    pub color: u8,
+    pub verticies: [Vector3D<DefaultNum>; 2],
+}
+
+impl Line {
+    // the outline of every polygon with shared edges only included once, takes the base color of the first polygon using it
+    pub fn edges(polygons: &[Polygon], materials: &[Material]) -> Vec<Line> {
+        let mut lines: Vec<Line> = Vec::new();
+        for poly in polygons {
+            for k in 0..3 {
+                let a = poly.verticies[k];
+                let b = poly.verticies[(k + 1) % 3];
+                let exists = lines
+                    .iter()
+                    .any(|line| line.verticies == [a, b] || line.verticies == [b, a]);
+                if !exists {
+                    let material = materials.get(poly.material as usize).unwrap_or(&MISSING);
+                    lines.push(Line {
+                        color: material.color(num!(1.0)),
+                        verticies: [a, b],
+                    });
+                }
+            }
+        }
+        lines
+    }
 }
 
 pub struct Mesh {
-    pub verticies: Vec<Polygon>,
+    // either borrowed from static data in rom or owned on the heap
+    pub verticies: Cow<'static, [Polygon]>,
+    // what the polygons' material indicies point at
+    pub materials: Cow<'static, [Material]>,
     pub pos: Vector3D<DefaultNum>,
     pub rot: Vector3D<DefaultNum>,
+    pub scale: Vector3D<DefaultNum>,
+    // blended into the polygons as they're drawn, see morph.rs
+    pub morph_targets: Vec<MorphTarget>,
+    // moves verticies with a skeleton after morphing, see skeleton.rs
+    pub skin: Option<Skin>,
+    // moved by Physics::step when it has one
+    pub body: Option<RigidBody>,
+    // coarser polygons the renderer swaps in as the mesh gets small, see lod.rs
+    pub lod: Option<Lod>,
+    // edges drawn on their own, for wireframe and vector style models
+    pub lines: Cow<'static, [Line]>,
+    // object space bounds, call recalculate_bounds after editing verticies
+    pub aabb: Aabb,
+    pub sphere: BoundingSphere,
+}
+
+impl Mesh {
+    pub fn new(
+        verticies: impl Into<Cow<'static, [Polygon]>>,
+        pos: Vector3D<DefaultNum>,
+        rot: Vector3D<DefaultNum>,
+    ) -> Mesh {
+        let mut mesh = Mesh {
+            verticies: verticies.into(),
+            materials: Cow::Borrowed(&[]),
+            lines: Cow::Borrowed(&[]),
+            pos,
+            rot,
+            scale: Vector3D::new(num!(1.0), num!(1.0), num!(1.0)),
+            morph_targets: Vec::new(),
+            skin: None,
+            body: None,
+            lod: None,
+            aabb: Aabb::default(),
+            sphere: BoundingSphere {
+                center: Vector3D::new(ZERO, ZERO, ZERO),
+                radius: ZERO,
+            },
+        };
+        mesh.recalculate_bounds();
+        mesh
+    }
+
+    pub fn recalculate_bounds(&mut self) {
+        self.aabb = Aabb::from_points(self.points());
+        self.sphere = BoundingSphere::from_points(self.points(), &self.aabb);
+    }
+
+    // every polygon and line vertex, shared ones come up more than once
+    pub fn points(&self) -> impl Iterator<Item = Vector3D<DefaultNum>> + '_ {
+        self.verticies
+            .iter()
+            .flat_map(|poly| poly.verticies)
+            .chain(self.lines.iter().flat_map(|line| line.verticies))
+    }
+
+    pub fn model_matrix(&self) -> Matrix {
+        Matrix::model(self.rot, self.scale)
+    }
+
+    pub fn world_aabb(&self) -> Aabb {
+        self.aabb.transformed(self.model_matrix(), self.pos)
+    }
+
+    pub fn world_sphere(&self) -> BoundingSphere {
+        self.sphere.transformed(self.model_matrix(), self.pos)
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform {
+            pos: self.pos,
+            rot: self.rot,
+            scale: self.scale,
+        }
+    }
+
+    // moves a world space point into the mesh's object space
+    pub fn to_object_space(&self, point: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        to_object_space(point, self.pos, self.rot, self.scale)
+    }
+}
+
+pub fn to_object_space(
+    point: Vector3D<DefaultNum>,
+    pos: Vector3D<DefaultNum>,
+    rot: Vector3D<DefaultNum>,
+    scale: Vector3D<DefaultNum>,
+) -> Vector3D<DefaultNum> {
+    // the rotation part is orthonormal so its transpose undoes it, the scale has to be divided back out
+    let unrotated = Matrix::rotation(rot).transpose().transform(point - pos);
+    Vector3D::new(
+        matrix::div(unrotated.x, scale.x),
+        matrix::div(unrotated.y, scale.y),
+        matrix::div(unrotated.z, scale.z),
+    )
 }
 
 pub struct Camera {
     pub pos: Vector3D<DefaultNum>,
     pub rot: Vector3D<DefaultNum>,
 }
+
+// where something sits in the world, the pos, rot and scale of a Mesh or QuantisedMesh together
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub pos: Vector3D<DefaultNum>,
+    pub rot: Vector3D<DefaultNum>,
+    pub scale: Vector3D<DefaultNum>,
+}
 pub struct Scene {
     pub camera: Camera,
-    pub mesh: Mesh,
+    // every object in the scene, each with its own transform
+    pub meshes: Vec<Mesh>,
 }
 
-impl Scene {
-    pub fn new() -> Scene {
-        Scene {
-            camera: Camera {
-                pos: Vector3D {
-                    x: ZERO,
-                    y: ZERO,
-                    z: ZERO,
-                },
-                rot: Vector3D {
-                    x: ZERO,
-                    y: ZERO,
-                    z: ZERO,
-                },
-            },
-            mesh: Mesh {
-                // cube model, stolen from some github.
-                verticies: vec![
-                    Polygon {
-                        color: 001,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 001,
-                        verticies: [
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 004,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 004,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 028,
-                        verticies: [
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 028,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 125,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 125,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 128,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 128,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 100,
-                        verticies: [
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(-24.0),
-                                z: num!(24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                        ],
-                    },
-                    Polygon {
-                        color: 100,
-                        verticies: [
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(24.0),
-                                y: num!(-24.0),
-                                z: num!(-24.0),
-                            },
-                            Vector3D {
-                                x: num!(-24.0),
-                                y: num!(24.0),
-                                z: num!(-24.0),
-                            },
-                        ],
-                    },
-                ],
-                pos: Vector3D {
-                    x: num!(0.0),
-                    y: num!(0.0),
-                    z: num!(-32.0),
-                },
-                rot: Vector3D {
-                    x: num!(0.0),
-                    y: num!(0.0),
-                    z: num!(0.0),
-                },
-            },
+// one per side of the cube. the demo palette isn't laid out in ramps, so these are single colors
+static CUBE_MATERIALS: [Material; 6] = [
+    Material::solid(&[1]),
+    Material::solid(&[4]),
+    Material::solid(&[28]),
+    Material::solid(&[125]),
+    Material::solid(&[128]),
+    Material::solid(&[100]),
+];
+
+// cube model, stolen from some github. the polygons live in rom, so building the scene doesn't copy them to the heap
+pub fn cube() -> Mesh {
+    let mut mesh = mesh! {
+        verticies: [
+            [24.0, -24.0, 24.0],
+            [-24.0, -24.0, 24.0],
+            [-24.0, -24.0, -24.0],
+            [-24.0, 24.0, -24.0],
+            [-24.0, 24.0, 24.0],
+            [24.0, 24.0, 24.0],
+            [24.0, 24.0, -24.0],
+            [24.0, -24.0, -24.0],
+        ],
+        faces: [
+            [0, 1, 2] => 0,
+            [3, 4, 5] => 0,
+            [6, 5, 0] => 1,
+            [5, 4, 1] => 1,
+            [1, 4, 3] => 2,
+            [7, 2, 3] => 2,
+            [7, 0, 2] => 3,
+            [6, 3, 5] => 3,
+            [7, 6, 0] => 4,
+            [0, 5, 1] => 4,
+            [2, 1, 3] => 5,
+            [6, 7, 3] => 5,
+        ],
+    };
+    mesh.materials = Cow::Borrowed(&CUBE_MATERIALS);
+    mesh
+}
+
+// 8 bit heights, row by row. embed one with include_heightmap! or make one with Heightmap::random
+pub struct Heightmap {
+    pub width: usize,
+    pub height: usize,
+    pub data: Cow<'static, [u8]>,
+}
+
+// faces get a material for the first band their average height is at or under
+#[derive(Clone, Copy, Debug)]
+pub struct HeightBand {
+    pub top: u8,
+    pub color: u8,
+}
+
+// water, grass, rock and snow
+pub const TERRAIN_BANDS: [HeightBand; 4] = [
+    HeightBand {
+        top: 48,
+        color: 125,
+    },
+    HeightBand {
+        top: 128,
+        color: 28,
+    },
+    HeightBand { top: 208, color: 4 },
+    HeightBand {
+        top: 255,
+        color: 100,
+    },
+];
+
+impl Heightmap {
+    // the format build.rs writes, width and height as little endian u16s then the heights
+    pub fn from_bytes(bytes: &'static [u8]) -> Heightmap {
+        let width = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        let height = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+        if bytes.len() != 4 + width * height {
+            panic!("Heightmap data is the wrong size.");
+        }
+        Heightmap {
+            width,
+            height,
+            data: Cow::Borrowed(&bytes[4..]),
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> u8) -> Heightmap {
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(f(x, y));
+            }
+        }
+        Heightmap {
+            width,
+            height,
+            data: Cow::Owned(data),
+        }
+    }
+
+    // random noise blurred a few times, more passes gives smoother hills
+    pub fn random(width: usize, height: usize, smoothing: usize) -> Heightmap {
+        let mut map = Heightmap::from_fn(width, height, |_, _| agb::rng::gen() as u8);
+        for _ in 0..smoothing {
+            let blurred = Heightmap::from_fn(width, height, |x, y| {
+                let mut total = 0;
+                let mut count = 0;
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                        total += map.get(nx, ny) as u32;
+                        count += 1;
+                    }
+                }
+                (total / count) as u8
+            });
+            map = blurred;
         }
+        map
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.data[y * self.width + x]
+    }
+
+    // a grid of two triangles per cell centered on the origin, heights go up (negative y).
+    // every cell is 2 polygons, so quantise big ones to fit them in ewram
+    pub fn to_mesh(
+        &self,
+        cell_size: DefaultNum,
+        height_scale: DefaultNum,
+        bands: &[HeightBand],
+    ) -> Mesh {
+        if self.width < 2 || self.height < 2 {
+            panic!("Heightmap is too small to make a mesh.");
+        }
+        let point = |x: usize, z: usize| Vector3D {
+            x: cell_size * (x as i32 * 2 - (self.width as i32 - 1)) / 2,
+            y: -height_scale * self.get(x, z) as i32,
+            z: cell_size * (z as i32 * 2 - (self.height as i32 - 1)) / 2,
+        };
+        // textures repeat once per cell
+        let uv = |x: usize, z: usize| {
+            Vector2D::new(DefaultNum::new(x as i32), DefaultNum::new(z as i32))
+        };
+        let material = |heights: [u8; 3]| {
+            let average = heights.iter().map(|h| *h as u32).sum::<u32>() / 3;
+            bands
+                .iter()
+                .position(|band| average <= band.top as u32)
+                .unwrap_or(bands.len().saturating_sub(1)) as u8
+        };
+
+        let mut polygons = Vec::with_capacity((self.width - 1) * (self.height - 1) * 2);
+        for z in 0..self.height - 1 {
+            for x in 0..self.width - 1 {
+                // corners go around the cell so both triangles face up
+                let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+                for tri in [[0, 1, 2], [0, 2, 3]] {
+                    let [a, b, c] = tri.map(|i| corners[i]);
+                    polygons.push(
+                        Polygon::new(
+                            material([self.get(a.0, a.1), self.get(b.0, b.1), self.get(c.0, c.1)]),
+                            [point(a.0, a.1), point(b.0, b.1), point(c.0, c.1)],
+                        )
+                        .with_uv([
+                            uv(a.0, a.1),
+                            uv(b.0, b.1),
+                            uv(c.0, c.1),
+                        ]),
+                    );
+                }
+            }
+        }
+
+        let mut mesh = Mesh::new(
+            polygons,
+            Vector3D::new(ZERO, ZERO, ZERO),
+            Vector3D::new(ZERO, ZERO, ZERO),
+        );
+        mesh.materials = bands
+            .iter()
+            .map(|band| Material {
+                ramp: vec![band.color].into(),
+                ..Material::solid(&[])
+            })
+            .collect::<Vec<Material>>()
+            .into();
+        mesh
     }
 }
 
@@ -331,3 +548,27 @@ impl Scene {
                         Vector3D {x: num!(24.0),   y: num!(24.0), z: num!(-24.0)},
                     ],
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn heightmap_mesh(_gba: &mut agb::Gba) {
+        let heights = [0, 100, 200, 255];
+        let map = Heightmap::from_fn(2, 2, |x, y| heights[y * 2 + x]);
+        let mesh = map.to_mesh(num!(16.0), num!(0.25), &TERRAIN_BANDS);
+
+        // one cell, two triangles, centered on the origin
+        assert_eq!(mesh.verticies.len(), 2);
+        assert_eq!(mesh.aabb.min.x, num!(-8.0));
+        assert_eq!(mesh.aabb.max.z, num!(8.0));
+        assert_eq!(mesh.aabb.min.y, num!(-63.75));
+        assert_eq!(mesh.materials.len(), TERRAIN_BANDS.len());
+
+        // 0, 100 and 255 average 118 which is grass, 0, 255 and 200 average 151 which is rock
+        let color = |poly: &Polygon| mesh.materials[poly.material as usize].ramp[0];
+        assert_eq!(color(&mesh.verticies[0]), 28);
+        assert_eq!(color(&mesh.verticies[1]), 4);
+    }
+}
@@ -0,0 +1,288 @@
+/*
+quickhull. starts from the biggest tetrahedron it can find in the points, then keeps taking the point
+furthest outside the hull, cutting away every face it can see and filling the hole with new faces that all
+meet at that point. the result is closed and wound the same way as everything else, so back face culling
+works on it.
+*/
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+// points closer to a face than this count as on it, stops rounding error making slivers
+const EPSILON: DefaultNum = DefaultNum::from_raw(64);
+
+struct Face {
+    verticies: [usize; 3],
+    // unit length, so distances to it don't overflow
+    normal: Vector3D<DefaultNum>,
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl Face {
+    fn new(points: &[Vector3D<DefaultNum>], verticies: [usize; 3]) -> Face {
+        Face {
+            verticies,
+            normal: face_normal(&verticies.map(|i| points[i])),
+            outside: Vec::new(),
+            alive: true,
+        }
+    }
+
+    fn distance(&self, points: &[Vector3D<DefaultNum>], point: Vector3D<DefaultNum>) -> DefaultNum {
+        self.normal.dot(point - points[self.verticies[0]])
+    }
+
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.verticies;
+        [(a, b), (b, c), (c, a)]
+    }
+}
+
+// the index of the point that scores highest
+fn furthest(
+    points: &[Vector3D<DefaultNum>],
+    candidates: impl Iterator<Item = usize>,
+    score: impl Fn(Vector3D<DefaultNum>) -> DefaultNum,
+) -> Option<(usize, DefaultNum)> {
+    candidates
+        .map(|i| (i, score(points[i])))
+        .max_by_key(|(_, score)| *score)
+}
+
+fn distance_to_line(
+    a: Vector3D<DefaultNum>,
+    b: Vector3D<DefaultNum>,
+    p: Vector3D<DefaultNum>,
+) -> DefaultNum {
+    let direction = (b - a).normalise();
+    let along = p - a;
+    (along - direction * direction.dot(along)).length()
+}
+
+// the four corners of the starting tetrahedron, None if the points are all flat
+fn simplex(points: &[Vector3D<DefaultNum>]) -> Option<[usize; 4]> {
+    // the furthest apart pair out of the points at the ends of each axis
+    let mut extremes = Vec::with_capacity(6);
+    for axis in 0..3 {
+        let get = |v: Vector3D<DefaultNum>| match axis {
+            0 => v.x,
+            1 => v.y,
+            _ => v.z,
+        };
+        extremes.push(furthest(points, 0..points.len(), |v| -get(v))?.0);
+        extremes.push(furthest(points, 0..points.len(), get)?.0);
+    }
+    let mut best = (0, 0, num!(0.0));
+    for &i in extremes.iter() {
+        for &j in extremes.iter() {
+            let d = (points[i] - points[j]).length();
+            if d > best.2 {
+                best = (i, j, d);
+            }
+        }
+    }
+    let (a, b, _) = best;
+    if a == b {
+        return None;
+    }
+
+    let (c, d) = furthest(points, 0..points.len(), |p| {
+        distance_to_line(points[a], points[b], p)
+    })?;
+    if d <= EPSILON {
+        return None;
+    }
+
+    let base = Face::new(points, [a, b, c]);
+    let (d, distance) = furthest(points, 0..points.len(), |p| base.distance(points, p).abs())?;
+    if distance <= EPSILON {
+        return None;
+    }
+    Some([a, b, c, d])
+}
+
+// a closed convex mesh around the points, None if there are fewer than 4 or they're all on one plane
+pub fn convex_hull(points: &[Vector3D<DefaultNum>], material: u8) -> Option<Mesh> {
+    let mut unique: Vec<Vector3D<DefaultNum>> = Vec::with_capacity(points.len());
+    for &point in points {
+        if !unique.contains(&point) {
+            unique.push(point);
+        }
+    }
+    let points = &unique[..];
+    let corners = simplex(points)?;
+
+    // wind every face so the middle of the tetrahedron is behind it
+    let center =
+        (points[corners[0]] + points[corners[1]] + points[corners[2]] + points[corners[3]])
+            * num!(0.25);
+    let mut faces: Vec<Face> = Vec::new();
+    for [a, b, c] in [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]] {
+        let mut face = Face::new(points, [corners[a], corners[b], corners[c]]);
+        if face.distance(points, center) > num!(0.0) {
+            face = Face::new(points, [corners[a], corners[c], corners[b]]);
+        }
+        faces.push(face);
+    }
+    let remaining: Vec<usize> = (0..points.len()).filter(|i| !corners.contains(i)).collect();
+    assign(points, &mut faces, 0, remaining);
+
+    while let Some(index) = faces
+        .iter()
+        .position(|face| face.alive && !face.outside.is_empty())
+    {
+        let face = &faces[index];
+        let (eye, _) = furthest(points, face.outside.iter().copied(), |p| {
+            face.distance(points, p)
+        })?;
+
+        let visible: Vec<usize> = (0..faces.len())
+            .filter(|&i| faces[i].alive && faces[i].distance(points, points[eye]) > EPSILON)
+            .collect();
+
+        // edges of the visible patch that aren't shared by two visible faces go round the hole
+        let mut horizon = Vec::new();
+        for &i in visible.iter() {
+            for (a, b) in faces[i].edges() {
+                let shared = visible.iter().any(|&j| faces[j].edges().contains(&(b, a)));
+                if !shared {
+                    horizon.push((a, b));
+                }
+            }
+        }
+
+        let mut orphans = Vec::new();
+        for &i in visible.iter() {
+            faces[i].alive = false;
+            orphans.append(&mut faces[i].outside);
+        }
+        orphans.retain(|&i| i != eye);
+
+        // same direction round as the face they replace, so the winding carries on
+        let first = faces.len();
+        for (a, b) in horizon {
+            faces.push(Face::new(points, [a, b, eye]));
+        }
+        assign(points, &mut faces, first, orphans);
+    }
+
+    let polygons: Vec<Polygon> = faces
+        .iter()
+        .filter(|face| face.alive)
+        .map(|face| Polygon::new(material, face.verticies.map(|i| points[i])))
+        .collect();
+    Some(Mesh::new(
+        polygons,
+        Vector3D::new(num!(0.0), num!(0.0), num!(0.0)),
+        Vector3D::new(num!(0.0), num!(0.0), num!(0.0)),
+    ))
+}
+
+// gives each point to the first face from `first` on that it's outside of, points inside are dropped
+fn assign(
+    points: &[Vector3D<DefaultNum>],
+    faces: &mut [Face],
+    first: usize,
+    candidates: Vec<usize>,
+) {
+    for i in candidates {
+        if let Some(face) = faces[first..]
+            .iter_mut()
+            .find(|face| face.distance(points, points[i]) > EPSILON)
+        {
+            face.outside.push(i);
+        }
+    }
+}
+
+impl Mesh {
+    // a convex stand in with the same transform and materials, for collision or culling against concave models
+    pub fn convex_hull(&self, material: u8) -> Option<Mesh> {
+        let points: Vec<Vector3D<DefaultNum>> = self.points().collect();
+        let mut hull = convex_hull(&points, material)?;
+        hull.materials = self.materials.clone();
+        hull.pos = self.pos;
+        hull.rot = self.rot;
+        hull.scale = self.scale;
+        Some(hull)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+    use alloc::vec;
+
+    fn point(x: i32, y: i32, z: i32) -> Vector3D<DefaultNum> {
+        Vector3D::new(DefaultNum::new(x), DefaultNum::new(y), DefaultNum::new(z))
+    }
+
+    fn corners() -> Vec<Vector3D<DefaultNum>> {
+        let mut points = Vec::new();
+        for x in [-10, 10] {
+            for y in [-10, 10] {
+                for z in [-10, 10] {
+                    points.push(point(x, y, z));
+                }
+            }
+        }
+        points
+    }
+
+    // a closed box, two triangles a side, all facing away from the middle
+    fn assert_box(hull: &Mesh) {
+        assert_eq!(hull.verticies.len(), 12);
+        assert_eq!(validate(&hull.verticies), vec![]);
+        for poly in hull.verticies.iter() {
+            assert!(poly.normal.dot(poly.verticies[0]) > num!(0.0));
+        }
+    }
+
+    #[test_case]
+    fn cube_with_inside_points(_gba: &mut agb::Gba) {
+        let mut points = corners();
+        points.extend([
+            point(0, 0, 0),
+            point(5, -3, 2),
+            point(-9, 9, -9),
+            point(10, 0, 0),
+        ]);
+        assert_box(&convex_hull(&points, 0).unwrap());
+    }
+
+    #[test_case]
+    fn duplicate_points(_gba: &mut agb::Gba) {
+        let mut points = corners();
+        points.extend(corners());
+        points.extend(corners());
+        assert_box(&convex_hull(&points, 0).unwrap());
+    }
+
+    #[test_case]
+    fn flat_points(_gba: &mut agb::Gba) {
+        let square = [
+            point(0, 0, 0),
+            point(10, 0, 0),
+            point(10, 10, 0),
+            point(0, 10, 0),
+            point(5, 5, 0),
+        ];
+        assert!(convex_hull(&square, 0).is_none());
+        let line = [
+            point(0, 0, 0),
+            point(1, 1, 1),
+            point(2, 2, 2),
+            point(3, 3, 3),
+        ];
+        assert!(convex_hull(&line, 0).is_none());
+        assert!(convex_hull(&[point(1, 2, 3); 6], 0).is_none());
+        assert!(convex_hull(&square[..3], 0).is_none());
+    }
+}
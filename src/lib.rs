@@ -9,9 +9,40 @@
 epic 3d cube demo to save bitmap modes from being removed from agbrs
 */
 
-mod drawing;
-mod geometry;
-mod matrix;
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+pub mod animation;
+pub mod backend;
+pub mod bounds;
+pub mod collision;
+pub mod depth;
+pub mod drawing;
+pub mod framebuffer;
+pub mod geometry;
+pub mod hull;
+pub mod lod;
+pub mod material;
+pub mod matrix;
+pub mod mesh_check;
+pub mod morph;
+pub mod painter;
+pub mod particles;
+pub mod physics;
+pub mod quantised;
+pub mod quantised_raw;
+pub mod render;
+pub mod scene_file;
+pub mod scene_format;
+pub mod skeleton;
+pub mod text;
+pub mod texture;
+pub mod validate;
+
+use alloc::vec;
+use alloc::vec::Vec;
 
 use agb::{
     display::{self},
@@ -19,33 +50,94 @@ use agb::{
     input::{Button, ButtonController},
     rng,
 };
+
+use animation::{Animation, FrameClock, Interpolation, Keyframe, PlayMode, Track};
+use backend::{Backend, Rgb15, Screen};
+use depth::{Depth, DepthBuffer};
 use drawing::*;
 use geometry::*;
-use geometry::*;
-use matrix::{DefaultNum, Matrix, MatrixMath};
+use matrix::{const_num, DefaultNum, Matrix, MatrixMath};
+use painter::Painter;
+use particles::Emitter;
+use physics::{Physics, RigidBody};
+use render::Renderer;
+
+// offsets from wherever the scene puts the cube
+static BOB: [Keyframe; 3] = [
+    Keyframe {
+        frame: 0,
+        value: Vector3D::new(const_num(0.0), const_num(0.0), const_num(0.0)),
+    },
+    Keyframe {
+        frame: 60,
+        value: Vector3D::new(const_num(0.0), const_num(-6.0), const_num(0.0)),
+    },
+    Keyframe {
+        frame: 120,
+        value: Vector3D::new(const_num(0.0), const_num(0.0), const_num(0.0)),
+    },
+];
 
 pub fn run(mut gba: agb::Gba) -> ! {
     // Setup gameboy
-    let mut scene = Scene::new();
-    let mut vram = gba.display.video.bitmap4();
+    let mut scene = include_scene!("cube").expect("build.rs writes the cube scene");
+    #[cfg(debug_assertions)]
+    validate::report("cube", &scene.meshes[0].verticies);
     let vblank = agb::interrupt::VBlank::get();
     let mut input = ButtonController::new();
+    // Backend::Mode3 for direct color, Backend::Mode5 for direct color at a lower resolution
+    let mut renderer = Renderer::with_backend(Backend::Mode4);
+    let mut vram = Screen::new(&mut gba.display.video, renderer.backend);
 
     // initialize colors
     for i in 0..256 {
-        vram.set_palette_entry(i, (i * 255).try_into().unwrap());
+        vram.set_palette_entry(i as u8, Rgb15((i * 255) as u16));
     }
 
     // just a few different color indexes for ease of use
     let red: u8 = 001;
     let yellow: u8 = 004;
-    let green: u8 = 028;
-    let blue: u8 = 125;
-    let cyan: u8 = 128;
     let white: u8 = 100;
     let black: u8 = 000;
     let inc: DefaultNum = num!(0.01);
-    let mut time: DefaultNum = num!(0.0);
+    let mut clock = FrameClock::new();
+    renderer.outline = Some(black);
+
+    // press A for sparks
+    let mut sparks = Emitter::new(scene.meshes[0].pos, vec![white, yellow, red]);
+    sparks.speed = num!(2.0);
+    sparks.spread = num!(1.0);
+    sparks.lifetime = 20;
+    sparks.lifetime_variance = 10;
+
+    // float the cube gently up and down
+    let mut bob = Animation::new(PlayMode::Loop);
+    let home = scene.meshes[0].pos;
+    let keyframes: Vec<Keyframe> = BOB
+        .iter()
+        .map(|key| Keyframe {
+            frame: key.frame,
+            value: home + key.value,
+        })
+        .collect();
+    bob.position = Some(Track::new(keyframes, Interpolation::Cubic));
+    bob.play(&clock);
+
+    // press B to drop the cube onto the floor instead
+    let mut physics = Physics::new();
+    physics.ground = Some(num!(60.0));
+    let mut dropped = false;
+
+    // press SELECT to turn the depth buffer on and off, it prints what it cost every second while it's on. it
+    // isn't made until the first press, there's no point holding 75KB for it otherwise
+    let (width, height) = renderer.backend.size();
+    let mut depth_timer = Some(gba.timers.timers().timer2);
+    let mut depth: Option<DepthBuffer> = None;
+    let mut use_depth = false;
+
+    // press START to sort every polygon back to front instead, cheaper than the depth buffer
+    let mut painter = Painter::new();
+    let mut use_painter = false;
 
     let mut x_rot = Matrix::new(3, 3);
     x_rot.elem[0][0] = num!(1.0);
@@ -61,97 +153,84 @@ pub fn run(mut gba: agb::Gba) -> ! {
         input.update();
 
         if input.is_pressed(Button::UP) {
-            scene.mesh.rot.x += inc;
+            scene.meshes[0].rot.x += inc;
         }
         if input.is_pressed(Button::DOWN) {
-            scene.mesh.rot.x -= inc;
+            scene.meshes[0].rot.x -= inc;
         }
         if input.is_pressed(Button::LEFT) {
-            scene.mesh.rot.z += inc;
+            scene.meshes[0].rot.z += inc;
         }
         if input.is_pressed(Button::RIGHT) {
-            scene.mesh.rot.z -= inc;
+            scene.meshes[0].rot.z -= inc;
         }
         if input.is_pressed(Button::L) {
-            scene.mesh.rot.y += inc;
+            scene.meshes[0].rot.y += inc;
         }
         if input.is_pressed(Button::R) {
-            scene.mesh.rot.y -= inc;
+            scene.meshes[0].rot.y -= inc;
+        }
+
+        if input.is_just_pressed(Button::A) {
+            sparks.pos = scene.meshes[0].pos;
+            sparks.burst(32);
+        }
+
+        if input.is_just_pressed(Button::SELECT) {
+            use_depth = !use_depth;
+            if let Some(timer) = depth_timer.take() {
+                depth = Some(DepthBuffer::new(width as usize, height as usize).with_timer(timer));
+            }
+        }
+        if input.is_just_pressed(Button::START) {
+            use_painter = !use_painter;
         }
 
-        time += inc;
-        if time > num!(1.0) {
-            time = num!(0.0);
+        if input.is_just_pressed(Button::B) {
+            let mut body = RigidBody::new(num!(1.0));
+            body.spin = Vector3D::new(num!(0.05), num!(0.02), num!(0.08));
+            scene.meshes[0].pos.y = num!(-60.0);
+            scene.meshes[0].body = Some(body);
+            dropped = true;
         }
 
-        let cos_x = scene.mesh.rot.x.cos();
-        let sin_x = scene.mesh.rot.x.sin();
-        let cos_y = scene.mesh.rot.y.cos();
-        let sin_y = scene.mesh.rot.y.sin();
-        let cos_z = scene.mesh.rot.z.cos();
-        let sin_z = scene.mesh.rot.z.sin();
-        let rot_matrix = Matrix {
-            height: 3,
-            width: 3,
-            // this is the rotation matrix to multiply all the verticies by
-            elem: [
-                [cos_x * cos_y, sin_x * cos_y, -sin_y, num!(0.0)],
-                [
-                    cos_x * sin_y * sin_z - sin_x * cos_z,
-                    sin_x * sin_y * sin_z + cos_x * cos_z,
-                    cos_y * sin_z,
-                    num!(0.0),
-                ],
-                [
-                    cos_x * sin_y * cos_z + sin_x * sin_z,
-                    sin_x * sin_y * cos_z - cos_x * sin_z,
-                    cos_y * cos_z,
-                    num!(0.0),
-                ],
-                [num!(0.0), num!(0.0), num!(0.0), num!(0.0)],
-            ],
-        };
+        clock.tick();
+        if dropped {
+            physics.step(&mut scene.meshes);
+        } else {
+            bob.apply(&clock, &mut scene.meshes[0]);
+        }
+        sparks.update();
 
         vram.clear(black);
-        for poly in &scene.mesh.verticies {
-            let transformed_tri = [
-                Matrix::from_vertex(poly.verticies[0]).mul(rot_matrix),
-                Matrix::from_vertex(poly.verticies[1]).mul(rot_matrix),
-                Matrix::from_vertex(poly.verticies[2]).mul(rot_matrix),
-            ];
-
-            let transformed_tri: Triangle3D<DefaultNum> = [
-                Matrix::to_vertex(transformed_tri[0]),
-                Matrix::to_vertex(transformed_tri[1]),
-                Matrix::to_vertex(transformed_tri[2]),
-            ];
-
-            let flat_tri = &to_triangle2D(&transformed_tri);
-
-            let x1 = flat_tri[0].x - flat_tri[1].x;
-            let x2 = flat_tri[0].x - flat_tri[2].x;
-            let y1 = flat_tri[0].y - flat_tri[1].y;
-            let y2 = flat_tri[0].y - flat_tri[2].y;
-            // this check simply checks if the triangle is facing the camera via back face culling. If it is, then draw it. This works since a cube is a convex shape.
-            if (x1 * y2 - x2 * y1) < num!(0.0) {
-                vram.draw_tri(flat_tri, poly.color);
-                ///*
-                vram.draw_line(flat_tri[0], flat_tri[1], black);
-                vram.draw_line(flat_tri[1], flat_tri[2], black);
-                vram.draw_line(flat_tri[2], flat_tri[0], black);
-                //*/
+        if let Some(depth) = depth.as_mut().filter(|_| use_depth) {
+            depth.clear();
+            let mut target = Depth::new(&mut vram, depth);
+            for mesh in scene.meshes.iter() {
+                renderer.draw_mesh(&mut target, &scene.camera, mesh);
+            }
+            if clock.frame % 60 == 0 {
+                depth.report();
+            }
+        } else if use_painter {
+            for mesh in scene.meshes.iter() {
+                renderer.draw_mesh(&mut painter, &scene.camera, mesh);
+            }
+            painter.flush(&mut vram);
+        } else {
+            for mesh in scene.meshes.iter() {
+                renderer.draw_mesh(&mut vram, &scene.camera, mesh);
             }
         }
+        renderer.draw_particles(&mut vram, &scene.camera, &sparks);
 
         vblank.wait_for_vblank();
-        vram.flip_page();
+        vram.present();
     }
 }
 
-fn to_triangle2D(tri: &Triangle3D<DefaultNum>) -> Triangle2D<DefaultNum> {
-    [
-        Vector2D::new(tri[0].x + 120, tri[0].y + 80),
-        Vector2D::new(tri[1].x + 120, tri[1].y + 80),
-        Vector2D::new(tri[2].x + 120, tri[2].y + 80),
-    ]
+#[cfg(test)]
+#[agb::entry]
+fn main(_gba: agb::Gba) -> ! {
+    loop {}
 }
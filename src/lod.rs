@@ -0,0 +1,131 @@
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LodMetric {
+    // thresholds are the projected radius in pixels a level takes over below
+    ScreenSize,
+    // thresholds are the distance from the camera a level takes over past
+    Distance,
+}
+
+pub struct LodLevel {
+    pub polygons: Cow<'static, [Polygon]>,
+    // where this level takes over from the one before it
+    pub threshold: DefaultNum,
+}
+
+// coarser stand ins for a mesh's own polygons, least coarse first. they share the mesh's materials, lines and
+// transform, and aren't morphed or skinned, so only give them to meshes that don't animate their verticies.
+pub struct Lod {
+    pub levels: Vec<LodLevel>,
+    pub metric: LodMetric,
+    // how far past a threshold the metric has to go before the level changes, stops flickering on the boundary
+    pub hysteresis: DefaultNum,
+    // 0 is the mesh's own polygons, n is levels[n - 1]. a cell so the renderer can update it while drawing
+    current: Cell<usize>,
+}
+
+impl Lod {
+    pub fn new(levels: Vec<LodLevel>, metric: LodMetric) -> Lod {
+        Lod {
+            levels,
+            metric,
+            hysteresis: num!(0.0),
+            current: Cell::new(0),
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current.get()
+    }
+
+    // true when the metric is far enough back on the detailed side of this level's threshold to leave it
+    fn finer(&self, measure: DefaultNum, level: usize) -> bool {
+        let threshold = self.levels[level].threshold;
+        match self.metric {
+            LodMetric::ScreenSize => measure >= threshold + self.hysteresis,
+            LodMetric::Distance => measure <= threshold - self.hysteresis,
+        }
+    }
+
+    fn coarser(&self, measure: DefaultNum, level: usize) -> bool {
+        let threshold = self.levels[level].threshold;
+        match self.metric {
+            LodMetric::ScreenSize => measure < threshold - self.hysteresis,
+            LodMetric::Distance => measure > threshold + self.hysteresis,
+        }
+    }
+
+    // steps from last frame's level towards the one the measure asks for, and remembers it
+    pub fn select(&self, measure: DefaultNum) -> usize {
+        let mut current = self.current.get().min(self.levels.len());
+        while current > 0 && self.finer(measure, current - 1) {
+            current -= 1;
+        }
+        while current < self.levels.len() && self.coarser(measure, current) {
+            current += 1;
+        }
+        self.current.set(current);
+        current
+    }
+}
+
+impl Mesh {
+    pub fn with_lod(mut self, lod: Lod) -> Mesh {
+        self.lod = Some(lod);
+        self
+    }
+
+    // the polygons for a detail level picked by Lod::select
+    pub fn level(&self, level: usize) -> &[Polygon] {
+        match (level, &self.lod) {
+            (0, _) | (_, None) => &self.verticies,
+            (n, Some(lod)) => &lod.levels[n - 1].polygons,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn lod(metric: LodMetric, thresholds: [DefaultNum; 2]) -> Lod {
+        let level = |threshold| LodLevel {
+            polygons: Cow::Borrowed(&[]),
+            threshold,
+        };
+        let mut lod = Lod::new(vec![level(thresholds[0]), level(thresholds[1])], metric);
+        lod.hysteresis = num!(2.0);
+        lod
+    }
+
+    #[test_case]
+    fn select_by_screen_size(_gba: &mut agb::Gba) {
+        let lod = lod(LodMetric::ScreenSize, [num!(20.0), num!(10.0)]);
+        assert_eq!(lod.select(num!(30.0)), 0);
+        assert_eq!(lod.select(num!(5.0)), 2);
+        // inside the hysteresis band it stays put
+        assert_eq!(lod.select(num!(11.0)), 2);
+        assert_eq!(lod.select(num!(15.0)), 1);
+        assert_eq!(lod.select(num!(21.0)), 1);
+        assert_eq!(lod.select(num!(22.0)), 0);
+    }
+
+    #[test_case]
+    fn select_by_distance(_gba: &mut agb::Gba) {
+        let lod = lod(LodMetric::Distance, [num!(20.0), num!(40.0)]);
+        assert_eq!(lod.select(num!(5.0)), 0);
+        assert_eq!(lod.select(num!(23.0)), 1);
+        assert_eq!(lod.select(num!(19.0)), 1);
+        assert_eq!(lod.select(num!(18.0)), 0);
+    }
+}
@@ -0,0 +1,226 @@
+/*
+mesh! builds a Mesh from a vertex list and faces that index into it. everything gets worked out while
+compiling, so the polygons end up in a static in rom and a bad index is a compile error rather than a crash.
+
+    let cube = mesh! {
+        verticies: [
+            [24.0, -24.0, 24.0],
+            ...
+        ],
+        // each face names the material it uses, the mesh's materials get set afterwards
+        faces: [
+            // triangle
+            [0, 1, 2] => 0,
+            // quads get split into two triangles, keeping the winding
+            [0, 1, 2, 3] => 1,
+        ],
+        // optional, edges drawn on their own in a palette color
+        lines: [
+            [0, 1] => 100,
+        ],
+    };
+*/
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[macro_export]
+macro_rules! mesh {
+    (
+        verticies: [$([$x:expr, $y:expr, $z:expr]),* $(,)?],
+        faces: [$([$($i:expr),+ $(,)?] => $material:expr),* $(,)?]
+        $(, lines: [$([$a:expr, $b:expr] => $line_color:expr),* $(,)?])? $(,)?
+    ) => {{
+        const VERTICIES: &[[f64; 3]] = &[$([$x as f64, $y as f64, $z as f64]),*];
+        const FACES: &[(&[usize], u8)] = &[$((&[$($i),+], $material)),*];
+        const COUNT: usize = $crate::macros::triangle_count(VERTICIES, FACES);
+        static POLYGONS: [$crate::geometry::Polygon; COUNT] =
+            $crate::macros::triangulate(VERTICIES, FACES);
+        const LINES: &[(usize, usize, u8)] = &[$($(($a, $b, $line_color)),*)?];
+        static LINE_DATA: [$crate::geometry::Line; LINES.len()] =
+            $crate::macros::lines(VERTICIES, LINES);
+        let mut mesh = $crate::geometry::Mesh::new(
+            &POLYGONS[..],
+            $crate::geometry::Vector3D::new(
+                $crate::matrix::const_num(0.0),
+                $crate::matrix::const_num(0.0),
+                $crate::matrix::const_num(0.0),
+            ),
+            $crate::geometry::Vector3D::new(
+                $crate::matrix::const_num(0.0),
+                $crate::matrix::const_num(0.0),
+                $crate::matrix::const_num(0.0),
+            ),
+        );
+        mesh.lines = (&LINE_DATA[..]).into();
+        mesh.recalculate_bounds();
+        mesh
+    }};
+}
+
+// embeds a heightmap that build.rs made from assets/heightmaps/<name>.png
+#[macro_export]
+macro_rules! include_heightmap {
+    ($name:literal) => {
+        $crate::geometry::Heightmap::from_bytes(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/heightmaps/",
+            $name,
+            ".bin"
+        )))
+    };
+}
+
+// embeds a texture that build.rs made from assets/textures/<name>.png, const so it can go in a static
+#[macro_export]
+macro_rules! include_texture {
+    ($name:literal) => {
+        $crate::texture::Texture::from_bytes(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/textures/",
+            $name,
+            ".bin"
+        )))
+    };
+}
+
+// embeds a scene that build.rs made from assets/scenes/<name>.obj, see scene_format.rs for the format
+#[macro_export]
+macro_rules! include_scene {
+    ($name:literal) => {
+        $crate::geometry::Scene::from_static(include_bytes!(concat!(
+            env!("OUT_DIR"),
+            "/scenes/",
+            $name,
+            ".scene"
+        )))
+    };
+}
+
+// embeds i16 QuantisedMeshes that build.rs made from assets/scenes/<name>.obj, an array with one for each object
+// in the file. the polygons go in statics, so nothing gets copied to the heap
+#[macro_export]
+macro_rules! include_quantised {
+    ($name:literal) => {{
+        use $crate::geometry::Vector3D;
+        use $crate::material::Material;
+        use $crate::matrix::DefaultNum;
+        use $crate::quantised::{QuantisedMesh, QuantisedPolygon};
+        include!(concat!(env!("OUT_DIR"), "/quantised/", $name, ".rs"))
+    }};
+}
+
+// also checks every face, since this is the first thing the macro evaluates
+pub const fn triangle_count(verticies: &[[f64; 3]], faces: &[(&[usize], u8)]) -> usize {
+    let mut count = 0;
+    let mut face = 0;
+    while face < faces.len() {
+        let indicies = faces[face].0;
+        let mut i = 0;
+        while i < indicies.len() {
+            if indicies[i] >= verticies.len() {
+                panic!("mesh! face uses a vertex index that doesn't exist.");
+            }
+            i += 1;
+        }
+        count += match indicies.len() {
+            3 => 1,
+            4 => 2,
+            _ => panic!("mesh! faces need 3 or 4 verticies."),
+        };
+        face += 1;
+    }
+    count
+}
+
+pub const fn triangulate<const N: usize>(
+    verticies: &[[f64; 3]],
+    faces: &[(&[usize], u8)],
+) -> [Polygon; N] {
+    let empty = Polygon::from_parts(
+        0,
+        [Vector3D::new(const_num(0.0), const_num(0.0), const_num(0.0)); 3],
+        Vector3D::new(const_num(0.0), const_num(0.0), const_num(0.0)),
+    );
+    let mut polygons = [empty; N];
+    let mut count = 0;
+    let mut face = 0;
+    while face < faces.len() {
+        let (indicies, material) = faces[face];
+        polygons[count] = triangle(verticies, [indicies[0], indicies[1], indicies[2]], material);
+        count += 1;
+        if indicies.len() == 4 {
+            polygons[count] =
+                triangle(verticies, [indicies[0], indicies[2], indicies[3]], material);
+            count += 1;
+        }
+        face += 1;
+    }
+    polygons
+}
+
+pub const fn lines<const N: usize>(
+    verticies: &[[f64; 3]],
+    lines: &[(usize, usize, u8)],
+) -> [Line; N] {
+    let empty = Line {
+        color: 0,
+        verticies: [Vector3D::new(const_num(0.0), const_num(0.0), const_num(0.0)); 2],
+    };
+    let mut output = [empty; N];
+    let mut i = 0;
+    while i < N {
+        let (a, b, color) = lines[i];
+        if a >= verticies.len() || b >= verticies.len() {
+            panic!("mesh! line uses a vertex index that doesn't exist.");
+        }
+        output[i] = Line {
+            color,
+            verticies: [point(verticies[a]), point(verticies[b])],
+        };
+        i += 1;
+    }
+    output
+}
+
+const fn triangle(verticies: &[[f64; 3]], indicies: [usize; 3], material: u8) -> Polygon {
+    let a = verticies[indicies[0]];
+    let b = verticies[indicies[1]];
+    let c = verticies[indicies[2]];
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = sqrt(n[0] * n[0] + n[1] * n[1] + n[2] * n[2]);
+    let length = if length == 0.0 { 1.0 } else { length };
+    Polygon::from_parts(
+        material,
+        [point(a), point(b), point(c)],
+        Vector3D::new(
+            const_num(n[0] / length),
+            const_num(n[1] / length),
+            const_num(n[2] / length),
+        ),
+    )
+}
+
+const fn point(v: [f64; 3]) -> Vector3D<DefaultNum> {
+    Vector3D::new(const_num(v[0]), const_num(v[1]), const_num(v[2]))
+}
+
+// f64::sqrt isn't const, newton's method gets there in plenty of time
+const fn sqrt(n: f64) -> f64 {
+    if n <= 0.0 {
+        return 0.0;
+    }
+    let mut x = if n > 1.0 { n } else { 1.0 };
+    let mut i = 0;
+    while i < 64 {
+        x = (x + n / x) * 0.5;
+        i += 1;
+    }
+    x
+}
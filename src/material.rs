@@ -0,0 +1,88 @@
+/*
+how a polygon looks. polygons only hold the index of a material in their mesh's list, so changing one
+material restyles every polygon that uses it.
+*/
+
+extern crate alloc;
+use alloc::borrow::Cow;
+
+use agb::fixnum::num;
+
+use crate::backend::Rgb15;
+use crate::geometry::*;
+use crate::matrix::*;
+use crate::texture::Texture;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shading {
+    // first color of the ramp, lights don't touch it
+    Unlit,
+    // one ramp step per polygon depending on how much it faces the light
+    Flat,
+    // like flat, and also fades down the ramp the further it is from the camera
+    Lit,
+    // just the edges, in the first color of the ramp
+    Wireframe,
+}
+
+#[derive(Clone, Debug)]
+pub struct Material {
+    // palette indicies from brightest to darkest
+    pub ramp: Cow<'static, [u8]>,
+    pub shading: Shading,
+    // which side of its polygons gets thrown away, CullMode::None draws both
+    pub cull: CullMode,
+    pub texture: Option<&'static Texture>,
+    // the fully lit color on screens that can show any color, shaded by scaling each channel. the ramp is
+    // still used everywhere else
+    pub rgb: Option<Rgb15>,
+}
+
+// what a polygon gets if its material index is past the end of the list, loud enough to notice
+pub static MISSING: Material = Material::solid(&[1]);
+
+impl Material {
+    pub const fn new(ramp: &'static [u8], shading: Shading) -> Material {
+        Material {
+            ramp: Cow::Borrowed(ramp),
+            shading,
+            cull: CullMode::Back,
+            texture: None,
+            rgb: None,
+        }
+    }
+
+    // one color, no lighting
+    pub const fn solid(ramp: &'static [u8]) -> Material {
+        Material::new(ramp, Shading::Unlit)
+    }
+
+    pub fn with_rgb(self, rgb: Rgb15) -> Material {
+        Material {
+            rgb: Some(rgb),
+            ..self
+        }
+    }
+
+    // brightness is 0 for fully dark up to 1 for fully lit
+    pub fn color(&self, brightness: DefaultNum) -> u8 {
+        if self.ramp.is_empty() {
+            return 0;
+        }
+        let last = self.ramp.len() as i32 - 1;
+        let darkness = num!(1.0) - brightness.clamp(num!(0.0), num!(1.0));
+        let step = (darkness * last + num!(0.5)).floor();
+        self.ramp[step.clamp(0, last) as usize]
+    }
+
+    // the same brightness as color but with 32 steps a channel, if the material has an rgb
+    pub fn rgb_color(&self, brightness: DefaultNum) -> Option<Rgb15> {
+        self.rgb.map(|rgb| rgb.scaled(brightness))
+    }
+}
+
+impl Mesh {
+    pub fn material(&self, index: u8) -> &Material {
+        self.materials.get(index as usize).unwrap_or(&MISSING)
+    }
+}
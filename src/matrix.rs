@@ -5,7 +5,7 @@
 #![cfg_attr(test, test_runner(agb::test_runner::test_runner))]
 #![deny(clippy::all)]
 
-use agb::fixnum::{FixedNum, Number};
+use agb::fixnum::{num, FixedNum, Number};
 use crate::Num;
 use crate::Vector3D;
 
@@ -42,10 +42,126 @@ impl Matrix {
             z: m.elem[2][0],
         }
     }
+
+    // rotation matrix for euler angles in turns, multiply verticies by this
+    pub fn rotation(rot: Vector3D<DefaultNum>) -> Matrix {
+        let cos_x = rot.x.cos();
+        let sin_x = rot.x.sin();
+        let cos_y = rot.y.cos();
+        let sin_y = rot.y.sin();
+        let cos_z = rot.z.cos();
+        let sin_z = rot.z.sin();
+        Matrix {
+            height: 3,
+            width: 3,
+            elem: [
+                [cos_x * cos_y, sin_x * cos_y, -sin_y, num!(0.0)],
+                [
+                    cos_x * sin_y * sin_z - sin_x * cos_z,
+                    sin_x * sin_y * sin_z + cos_x * cos_z,
+                    cos_y * sin_z,
+                    num!(0.0),
+                ],
+                [
+                    cos_x * sin_y * cos_z + sin_x * sin_z,
+                    sin_x * sin_y * cos_z - cos_x * sin_z,
+                    cos_y * cos_z,
+                    num!(0.0),
+                ],
+                [num!(0.0), num!(0.0), num!(0.0), num!(0.0)],
+            ],
+        }
+    }
+
+    // rotation with the scale applied first, this is what takes a mesh from object space to world space
+    pub fn model(rot: Vector3D<DefaultNum>, scale: Vector3D<DefaultNum>) -> Matrix {
+        let mut output = Matrix::rotation(rot);
+        for row in output.elem.iter_mut().take(3) {
+            row[0] *= scale.x;
+            row[1] *= scale.y;
+            row[2] *= scale.z;
+        }
+        output
+    }
+
+    // rotation then a move, as a 4x4 so a chain of them can be put together with mul
+    pub fn affine(rot: Vector3D<DefaultNum>, pos: Vector3D<DefaultNum>) -> Matrix {
+        let mut output = Matrix::rotation(rot);
+        output.width = 4;
+        output.height = 4;
+        output.elem[0][3] = pos.x;
+        output.elem[1][3] = pos.y;
+        output.elem[2][3] = pos.z;
+        output.elem[3][3] = num!(1.0);
+        output
+    }
+
+    pub fn translation(&self) -> Vector3D<DefaultNum> {
+        Vector3D {
+            x: self.elem[0][3],
+            y: self.elem[1][3],
+            z: self.elem[2][3],
+        }
+    }
+
+    // only works for affine matricies made of rotations and moves, which is all bones use
+    pub fn rigid_inverse(&self) -> Matrix {
+        let mut output = Matrix::new(4, 4);
+        for x in 0..3 {
+            for y in 0..3 {
+                output.elem[x][y] = self.elem[y][x];
+            }
+        }
+        let t = self.translation();
+        for x in 0..3 {
+            output.elem[x][3] =
+                -(output.elem[x][0] * t.x + output.elem[x][1] * t.y + output.elem[x][2] * t.z);
+        }
+        output.elem[3][3] = num!(1.0);
+        output
+    }
+}
+
+// num! can't be used in const or static data, this can
+pub const fn const_num(n: f64) -> DefaultNum {
+    DefaultNum::from_raw((n * 4096.0) as i32)
+}
+
+// Num's own division shifts before dividing and overflows for anything over 128, so widen first
+pub fn div(a: DefaultNum, b: DefaultNum) -> DefaultNum {
+    DefaultNum::from_raw((((a.to_raw() as i64) << 12) / b.to_raw() as i64) as i32)
+}
+
+pub fn sqrt(n: DefaultNum) -> DefaultNum {
+    // square root of the raw value shifted up, so the result keeps its 12 fractional bits
+    DefaultNum::from_raw(isqrt((n.to_raw().max(0) as u64) << 12) as i32)
+}
+
+pub fn isqrt(value: u64) -> u64 {
+    let mut result: u64 = 0;
+    let mut bit: u64 = 1 << 62;
+    while bit > value {
+        bit >>= 2;
+    }
+    let mut rem = value;
+    while bit != 0 {
+        if rem >= result + bit {
+            rem -= result + bit;
+            result = (result >> 1) + bit;
+        } else {
+            result >>= 1;
+        }
+        bit >>= 2;
+    }
+    result
 }
 
 pub trait MatrixMath {
     fn mul(&self, rhs: Matrix) -> Matrix;
+    fn transpose(&self) -> Matrix;
+    fn transform(&self, v: Vector3D<DefaultNum>) -> Vector3D<DefaultNum>;
+    fn transform_point(&self, v: Vector3D<DefaultNum>) -> Vector3D<DefaultNum>;
+    fn rotate(&self, v: Vector3D<DefaultNum>) -> Vector3D<DefaultNum>;
 }
 
 /*
@@ -72,4 +188,33 @@ impl MatrixMath for Matrix {
         }
         product
     }
+
+    fn transpose(&self) -> Matrix {
+        let mut output = Matrix::new(self.width, self.height);
+        for x in 0..4 {
+            for y in 0..4 {
+                output.elem[x][y] = self.elem[y][x];
+            }
+        }
+        output
+    }
+
+    fn transform(&self, v: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        Matrix::to_vertex(Matrix::from_vertex(v).mul(*self))
+    }
+
+    // for 4x4 affine matricies, includes the translation
+    fn transform_point(&self, v: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        self.rotate(v) + self.translation()
+    }
+
+    // just the 3x3 part, for directions like normals
+    fn rotate(&self, v: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        let e = self.elem;
+        Vector3D {
+            x: e[0][0] * v.x + e[0][1] * v.y + e[0][2] * v.z,
+            y: e[1][0] * v.x + e[1][1] * v.y + e[1][2] * v.z,
+            z: e[2][0] * v.x + e[2][1] * v.y + e[2][2] * v.z,
+        }
+    }
 }
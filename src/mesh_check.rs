@@ -0,0 +1,112 @@
+/*
+the checks behind validate.rs, done on raw DefaultNum positions (i32s with 12 fractional bits). build.rs pulls
+this file in with #[path] to run exactly the same checks on the models it imports, so it can only use core and
+alloc.
+*/
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+pub type RawPoint = [i32; 3];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Issue {
+    // both triangles walk their shared edge in the same direction, so one of them faces the wrong way
+    InconsistentWinding(usize, usize),
+    ZeroArea(usize),
+    DuplicateFace(usize, usize),
+    // more than two triangles share the edge between these two points
+    NonManifoldEdge([RawPoint; 2]),
+    // only one triangle uses this edge, the mesh has a hole in it
+    OpenEdge(usize),
+}
+
+impl Issue {
+    // open and non-manifold edges are fine on purpose sometimes, like a flat ground or blocks touching at a
+    // corner. the rest always draw wrong
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Issue::OpenEdge(_) | Issue::NonManifoldEdge(_))
+    }
+}
+
+// triangles refer to points by position, so weld equal positions into shared indicies first. also gives back
+// the position each index stands for
+pub fn weld(triangles: &[[RawPoint; 3]]) -> (Vec<[usize; 3]>, Vec<RawPoint>) {
+    let mut indicies: BTreeMap<RawPoint, usize> = BTreeMap::new();
+    let mut points = Vec::new();
+    let faces = triangles
+        .iter()
+        .map(|tri| {
+            tri.map(|p| {
+                *indicies.entry(p).or_insert_with(|| {
+                    points.push(p);
+                    points.len() - 1
+                })
+            })
+        })
+        .collect();
+    (faces, points)
+}
+
+// every undirected edge mapped to the triangles using it, and which way each of them walks it
+pub fn edges(faces: &[[usize; 3]]) -> BTreeMap<(usize, usize), Vec<(usize, bool)>> {
+    let mut edges: BTreeMap<(usize, usize), Vec<(usize, bool)>> = BTreeMap::new();
+    for (face, idx) in faces.iter().enumerate() {
+        for k in 0..3 {
+            let (a, b) = (idx[k], idx[(k + 1) % 3]);
+            if a == b {
+                continue;
+            }
+            edges
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push((face, a < b));
+        }
+    }
+    edges
+}
+
+fn is_zero_area(tri: &[RawPoint; 3]) -> bool {
+    // exact, and widened so large triangles can't overflow
+    let [p, q, r] = tri.map(|p| p.map(|n| n as i64));
+    let a = [q[0] - p[0], q[1] - p[1], q[2] - p[2]];
+    let b = [r[0] - p[0], r[1] - p[1], r[2] - p[2]];
+    a[1] * b[2] - a[2] * b[1] == 0
+        && a[2] * b[0] - a[0] * b[2] == 0
+        && a[0] * b[1] - a[1] * b[0] == 0
+}
+
+pub fn check(triangles: &[[RawPoint; 3]]) -> Vec<Issue> {
+    let (faces, points) = weld(triangles);
+    let mut issues = Vec::new();
+
+    for (i, tri) in triangles.iter().enumerate() {
+        if is_zero_area(tri) {
+            issues.push(Issue::ZeroArea(i));
+        }
+    }
+
+    let mut seen: BTreeMap<[usize; 3], usize> = BTreeMap::new();
+    for (i, idx) in faces.iter().enumerate() {
+        let mut key = *idx;
+        key.sort_unstable();
+        if let Some(first) = seen.insert(key, i) {
+            issues.push(Issue::DuplicateFace(first, i));
+        }
+    }
+
+    for (&(start, end), users) in edges(&faces).iter() {
+        match users.as_slice() {
+            [(face, _)] => issues.push(Issue::OpenEdge(*face)),
+            [(a, a_dir), (b, b_dir)] => {
+                if a_dir == b_dir {
+                    issues.push(Issue::InconsistentWinding(*a, *b));
+                }
+            }
+            _ => issues.push(Issue::NonManifoldEdge([points[start], points[end]])),
+        }
+    }
+
+    issues
+}
@@ -0,0 +1,72 @@
+extern crate alloc;
+use alloc::borrow::Cow;
+
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+// another shape for the same mesh. at weight 1 the mesh becomes this shape, at 0 it's untouched
+pub struct MorphTarget {
+    // one triangle per polygon in the mesh, in the same order
+    pub verticies: Cow<'static, [Triangle3D<DefaultNum>]>,
+    pub weight: DefaultNum,
+}
+
+impl MorphTarget {
+    pub fn new(verticies: impl Into<Cow<'static, [Triangle3D<DefaultNum>]>>) -> MorphTarget {
+        MorphTarget {
+            verticies: verticies.into(),
+            weight: num!(0.0),
+        }
+    }
+
+    // handy for building targets by editing a copy of the mesh
+    pub fn from_polygons(polygons: &[Polygon]) -> MorphTarget {
+        MorphTarget::new(
+            polygons
+                .iter()
+                .map(|poly| poly.verticies)
+                .collect::<alloc::vec::Vec<_>>(),
+        )
+    }
+}
+
+impl Mesh {
+    pub fn add_morph_target(&mut self, target: MorphTarget) {
+        if target.verticies.len() != self.verticies.len() {
+            panic!("Morph target doesn't match the mesh.");
+        }
+        self.morph_targets.push(target);
+    }
+
+    pub fn is_morphing(&self) -> bool {
+        self.morph_targets
+            .iter()
+            .any(|target| target.weight != num!(0.0))
+    }
+
+    // the polygon at this index with every weighted target blended in. the normal is worked out again
+    // since the shape changed, the cached bounds are not, so keep targets inside the base mesh's bounds.
+    pub fn morphed(&self, index: usize) -> Polygon {
+        let base = self.verticies[index];
+        let mut verticies = base.verticies;
+        for target in &self.morph_targets {
+            if target.weight == num!(0.0) {
+                continue;
+            }
+            let shape = target.verticies[index];
+            for (v, (from, to)) in verticies
+                .iter_mut()
+                .zip(base.verticies.iter().zip(shape.iter()))
+            {
+                *v = *v + (*to - *from) * target.weight;
+            }
+        }
+        Polygon {
+            verticies,
+            normal: face_normal(&verticies),
+            ..base
+        }
+    }
+}
@@ -0,0 +1,187 @@
+/*
+the painter's algorithm, a cheaper way than a depth buffer to get meshes that aren't convex and objects that
+overlap looking right. draw everything for the frame into a Painter instead of the screen, then flush it:
+
+    for mesh in meshes.iter() {
+        renderer.draw_mesh(&mut painter, &camera, mesh);
+    }
+    painter.flush(&mut vram);
+
+flush sorts everything that was drawn from back to front and draws it again onto the real target. it can still
+get it wrong where polygons cut through each other or are much bigger than their neighbours, a depth buffer
+doesn't have that problem.
+*/
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use agb::fixnum::Vector2D;
+
+use crate::backend::Rgb15;
+use crate::depth::DepthBuffer;
+use crate::drawing::*;
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortKey {
+    // middle of the polygon, right most of the time
+    Average,
+    // furthest corner, better for long polygons that reach out past the ones in front of them
+    Farthest,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Queued {
+    Tri(Triangle2D<DefaultNum>, u8),
+    TriRgb(Triangle2D<DefaultNum>, Rgb15, u8),
+    Line(Vector2D<DefaultNum>, Vector2D<DefaultNum>, u8),
+    Dot(Vector2D<DefaultNum>, u8),
+}
+
+pub struct Painter {
+    pub sort: SortKey,
+    // whatever gets drawn next is this far away, bigger is closer
+    key: u16,
+    queue: Vec<(u16, Queued)>,
+    // the other half of each radix pass. kept between frames along with the queue so nothing gets reallocated
+    sorted: Vec<(u16, Queued)>,
+}
+
+impl Painter {
+    pub fn new() -> Painter {
+        Painter {
+            sort: SortKey::Average,
+            key: 0,
+            queue: Vec::new(),
+            sorted: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // draws everything queued since the last flush onto target, furthest first, and empties the queue
+    pub fn flush<D: Draw>(&mut self, target: &mut D) {
+        self.sort_queue();
+        for (_, item) in self.queue.drain(..) {
+            match item {
+                Queued::Tri(tri, color) => target.draw_tri(&tri, color),
+                // already sorted, so the depth doesn't matter any more
+                Queued::TriRgb(tri, color, index) => {
+                    target.draw_tri_rgb(&tri, [DefaultNum::new(0); 3], color, index)
+                }
+                Queued::Line(a, b, color) => target.draw_line(a, b, color),
+                Queued::Dot(p, color) => target.draw_dot(p, color),
+            }
+        }
+        self.key = 0;
+    }
+
+    // least significant byte then most, a counting sort each time. both passes are stable so a polygon's
+    // outline stays after its fill
+    fn sort_queue(&mut self) {
+        for shift in [0, 8] {
+            let mut starts = [0usize; 256];
+            for (key, _) in self.queue.iter() {
+                starts[((key >> shift) & 255) as usize] += 1;
+            }
+            let mut total = 0;
+            for start in starts.iter_mut() {
+                let count = *start;
+                *start = total;
+                total += count;
+            }
+
+            self.sorted.clear();
+            self.sorted.extend_from_slice(&self.queue);
+            for item in self.queue.iter() {
+                let bucket = &mut starts[((item.0 >> shift) & 255) as usize];
+                self.sorted[*bucket] = *item;
+                *bucket += 1;
+            }
+            core::mem::swap(&mut self.queue, &mut self.sorted);
+        }
+    }
+}
+
+impl Draw for Painter {
+    fn draw_dot(&mut self, p: Vector2D<DefaultNum>, color: u8) {
+        self.queue.push((self.key, Queued::Dot(p, color)));
+    }
+    fn draw_line(&mut self, a: Vector2D<DefaultNum>, b: Vector2D<DefaultNum>, color: u8) {
+        self.queue.push((self.key, Queued::Line(a, b, color)));
+    }
+    fn draw_tri(&mut self, tri: &Triangle2D<DefaultNum>, color: u8) {
+        self.queue.push((self.key, Queued::Tri(*tri, color)));
+    }
+    fn draw_tri_rgb(
+        &mut self,
+        tri: &Triangle2D<DefaultNum>,
+        _z: [DefaultNum; 3],
+        color: Rgb15,
+        index: u8,
+    ) {
+        self.queue
+            .push((self.key, Queued::TriRgb(*tri, color, index)));
+    }
+    fn depth_hint(&mut self, z: [DefaultNum; 3]) {
+        let z = match self.sort {
+            SortKey::Average => (z[0] + z[1] + z[2]) / 3,
+            SortKey::Farthest => z[0].max(z[1]).max(z[2]),
+        };
+        self.key = DepthBuffer::key(z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agb::fixnum::num;
+    use alloc::vec;
+
+    // what flush drew, in order
+    struct Record(Vec<(&'static str, u8)>);
+
+    impl Draw for Record {
+        fn draw_dot(&mut self, _p: Vector2D<DefaultNum>, color: u8) {
+            self.0.push(("dot", color));
+        }
+        fn draw_line(&mut self, _a: Vector2D<DefaultNum>, _b: Vector2D<DefaultNum>, color: u8) {
+            self.0.push(("line", color));
+        }
+        fn draw_tri(&mut self, _tri: &Triangle2D<DefaultNum>, color: u8) {
+            self.0.push(("tri", color));
+        }
+    }
+
+    #[test_case]
+    fn far_first_outlines_after_fills(_gba: &mut agb::Gba) {
+        let tri = [
+            Vector2D::new(num!(0.0), num!(0.0)),
+            Vector2D::new(num!(8.0), num!(0.0)),
+            Vector2D::new(num!(0.0), num!(8.0)),
+        ];
+        let mut painter = Painter::new();
+        // the near one goes in first, and both keys differ in their low and high bytes
+        painter.depth_hint([num!(32.0); 3]);
+        painter.draw_tri(&tri, 1);
+        painter.draw_line(tri[0], tri[1], 2);
+        painter.depth_hint([num!(96.0); 3]);
+        painter.draw_tri(&tri, 3);
+        painter.draw_line(tri[0], tri[1], 4);
+
+        let mut record = Record(vec![]);
+        painter.flush(&mut record);
+        assert_eq!(
+            record.0,
+            vec![("tri", 3), ("line", 4), ("tri", 1), ("line", 2)]
+        );
+        assert!(painter.is_empty());
+    }
+}
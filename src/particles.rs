@@ -0,0 +1,123 @@
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Particle {
+    pub pos: Vector3D<DefaultNum>,
+    pub vel: Vector3D<DefaultNum>,
+    pub age: u16,
+    pub lifetime: u16,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleShape {
+    // a single pixel, the cheapest thing there is
+    Point,
+    // a square this big in world units, shrinks with distance like everything else
+    Quad(DefaultNum),
+}
+
+pub struct Emitter {
+    pub pos: Vector3D<DefaultNum>,
+    // doesn't need to be unit length
+    pub direction: Vector3D<DefaultNum>,
+    // how far particles can stray from the direction, 0 is a straight line and 1 is about 45 degrees
+    pub spread: DefaultNum,
+    pub speed: DefaultNum,
+    // particles per frame, fractions build up over several frames
+    pub rate: DefaultNum,
+    // in frames, each particle gets up to lifetime_variance more
+    pub lifetime: u16,
+    pub lifetime_variance: u16,
+    // added to every particle's velocity each frame
+    pub gravity: Vector3D<DefaultNum>,
+    // palette indicies a particle fades through from birth to death
+    pub ramp: Cow<'static, [u8]>,
+    pub shape: ParticleShape,
+    pub max_particles: usize,
+    pub particles: Vec<Particle>,
+    pending: DefaultNum,
+}
+
+// -1 to 1
+fn random() -> DefaultNum {
+    DefaultNum::from_raw(agb::rng::gen() >> 19)
+}
+
+impl Emitter {
+    pub fn new(pos: Vector3D<DefaultNum>, ramp: impl Into<Cow<'static, [u8]>>) -> Emitter {
+        Emitter {
+            pos,
+            direction: Vector3D::new(num!(0.0), num!(-1.0), num!(0.0)),
+            spread: num!(0.5),
+            speed: num!(1.0),
+            rate: num!(0.0),
+            lifetime: 30,
+            lifetime_variance: 0,
+            gravity: Vector3D::new(num!(0.0), num!(0.05), num!(0.0)),
+            ramp: ramp.into(),
+            shape: ParticleShape::Point,
+            max_particles: 64,
+            particles: Vec::new(),
+            pending: num!(0.0),
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.max_particles {
+            return;
+        }
+        let wobble = Vector3D::new(random(), random(), random()) * self.spread;
+        let vel = (self.direction.normalise() + wobble).normalise() * self.speed;
+        let variance = if self.lifetime_variance > 0 {
+            (agb::rng::gen() as u32 % (self.lifetime_variance as u32 + 1)) as u16
+        } else {
+            0
+        };
+        self.particles.push(Particle {
+            pos: self.pos,
+            vel,
+            age: 0,
+            lifetime: self.lifetime.saturating_add(variance).max(1),
+        });
+    }
+
+    // all at once, for explosions
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+
+    // call once a frame
+    pub fn update(&mut self) {
+        self.pending += self.rate;
+        while self.pending >= num!(1.0) {
+            self.pending -= num!(1.0);
+            self.spawn();
+        }
+
+        let gravity = self.gravity;
+        for particle in self.particles.iter_mut() {
+            particle.vel = particle.vel + gravity;
+            particle.pos = particle.pos + particle.vel;
+            particle.age += 1;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    pub fn color(&self, particle: &Particle) -> u8 {
+        if self.ramp.is_empty() {
+            return 0;
+        }
+        let step = particle.age as usize * self.ramp.len() / particle.lifetime as usize;
+        self.ramp[step.min(self.ramp.len() - 1)]
+    }
+}
@@ -0,0 +1,342 @@
+/*
+rigid bodies that move the meshes they're attached to. everything is per frame, so velocities are units per
+frame and gravity is units per frame per frame. each frame gets split into a few fixed substeps so fast things
+don't sink through the ground.
+
+bodies are treated as solid spheres the size of their bounding sphere when they spin, and the spin is added
+straight onto the mesh's euler angles. neither is exact but both are cheap and look right.
+*/
+
+use agb::fixnum::num;
+
+use crate::bounds::Aabb;
+use crate::collision;
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RigidBody {
+    pub vel: Vector3D<DefaultNum>,
+    // about the world axes, radians per frame
+    pub spin: Vector3D<DefaultNum>,
+    // 0 for things that never move, like walls
+    pub inverse_mass: DefaultNum,
+    // 0 stops dead, 1 bounces back as fast as it came
+    pub restitution: DefaultNum,
+    // how much sliding turns into rolling
+    pub friction: DefaultNum,
+    // pulls on this body instead of Physics::gravity, for balloons and things on strings
+    pub gravity: Option<Vector3D<DefaultNum>>,
+}
+
+impl RigidBody {
+    // use fixed for something that should never move
+    pub fn new(mass: DefaultNum) -> RigidBody {
+        assert!(mass > num!(0.0), "Rigid bodies need a mass above 0.");
+        RigidBody {
+            inverse_mass: div(num!(1.0), mass),
+            ..RigidBody::fixed()
+        }
+    }
+
+    pub fn fixed() -> RigidBody {
+        RigidBody {
+            vel: Vector3D::new(num!(0.0), num!(0.0), num!(0.0)),
+            spin: Vector3D::new(num!(0.0), num!(0.0), num!(0.0)),
+            inverse_mass: num!(0.0),
+            restitution: num!(0.5),
+            friction: num!(0.4),
+            gravity: None,
+        }
+    }
+
+    fn velocity_at(&self, r: Vector3D<DefaultNum>) -> Vector3D<DefaultNum> {
+        self.vel + self.spin.cross(r)
+    }
+
+    // how much an impulse along n at r pushes back, the bigger this is the less the body gives
+    fn response(
+        &self,
+        r: Vector3D<DefaultNum>,
+        n: Vector3D<DefaultNum>,
+        radius: DefaultNum,
+    ) -> DefaultNum {
+        if self.inverse_mass == num!(0.0) {
+            return num!(0.0);
+        }
+        // a point can't be spun, so all of the impulse goes into moving it
+        if radius == num!(0.0) {
+            return self.inverse_mass;
+        }
+        // a solid sphere's inertia is 2/5 m r^2, kept as a ratio so it doesn't underflow
+        let arm = r.cross(n);
+        self.inverse_mass * (num!(1.0) + div(arm.dot(arm), radius * radius) * num!(2.5))
+    }
+
+    fn apply_impulse(
+        &mut self,
+        r: Vector3D<DefaultNum>,
+        impulse: Vector3D<DefaultNum>,
+        radius: DefaultNum,
+    ) {
+        self.vel = self.vel + impulse * self.inverse_mass;
+        if radius == num!(0.0) {
+            return;
+        }
+        let torque = r.cross(impulse) * (self.inverse_mass * num!(2.5));
+        let r2 = radius * radius;
+        self.spin =
+            self.spin + Vector3D::new(div(torque.x, r2), div(torque.y, r2), div(torque.z, r2));
+    }
+}
+
+pub struct Physics {
+    // for bodies that don't set their own
+    pub gravity: Vector3D<DefaultNum>,
+    // height of a flat floor, remember y points down so things above it have a smaller y
+    pub ground: Option<DefaultNum>,
+    pub substeps: u8,
+    // closing speeds under this don't bounce, so resting things settle instead of jittering
+    pub settle_speed: DefaultNum,
+}
+
+impl Physics {
+    pub fn new() -> Physics {
+        Physics {
+            gravity: Vector3D::new(num!(0.0), num!(0.2), num!(0.0)),
+            ground: None,
+            substeps: 4,
+            settle_speed: num!(0.5),
+        }
+    }
+
+    // call once a frame, meshes without a body are left alone but still get hit by the ones with them
+    pub fn step(&self, meshes: &mut [Mesh]) {
+        let dt = num!(1.0) / self.substeps.max(1) as i32;
+        for _ in 0..self.substeps.max(1) {
+            for mesh in meshes.iter_mut() {
+                let mut body = match mesh.body {
+                    Some(body) if body.inverse_mass != num!(0.0) => body,
+                    _ => continue,
+                };
+                body.vel = body.vel + body.gravity.unwrap_or(self.gravity) * dt;
+                mesh.pos = mesh.pos + body.vel * dt;
+                // the euler angles turn about z, y then x, backwards, see Matrix::rotation
+                let turns = body.spin * (dt * num!(0.159155));
+                mesh.rot = mesh.rot - Vector3D::new(turns.z, turns.y, turns.x);
+                if let Some(ground) = self.ground {
+                    self.hit_ground(mesh, &mut body, ground);
+                }
+                mesh.body = Some(body);
+            }
+
+            for j in 1..meshes.len() {
+                let (left, right) = meshes.split_at_mut(j);
+                for a in left.iter_mut() {
+                    self.hit_each_other(a, &mut right[0]);
+                }
+            }
+        }
+    }
+
+    fn hit_ground(&self, mesh: &mut Mesh, body: &mut RigidBody, ground: DefaultNum) {
+        if mesh.world_aabb().max.y <= ground {
+            return;
+        }
+        let model = mesh.model_matrix();
+        // the middle of everything under the ground rather than the average, points() repeats shared corners a
+        // different number of times each and that would spin things that land flat
+        let mut below = mesh
+            .points()
+            .map(|v| model.transform(v) + mesh.pos)
+            .filter(|v| v.y > ground)
+            .peekable();
+        if below.peek().is_none() {
+            return;
+        }
+        let below = Aabb::from_points(below);
+        let depth = below.max.y - ground;
+        let point = below.center();
+        mesh.pos.y -= depth;
+
+        let radius = mesh.world_sphere().radius;
+        let n = Vector3D::new(num!(0.0), num!(1.0), num!(0.0));
+        let r = point - mesh.pos;
+        let v = body.velocity_at(r);
+        let closing = v.dot(n);
+        if closing <= num!(0.0) {
+            return;
+        }
+        let bounce = if closing < self.settle_speed {
+            num!(0.0)
+        } else {
+            body.restitution
+        };
+        let j = div(closing * (num!(1.0) + bounce), body.response(r, n, radius));
+        body.apply_impulse(r, -n * j, radius);
+        friction(body, r, radius, None, v - n * closing, j);
+    }
+
+    fn hit_each_other(&self, a: &mut Mesh, b: &mut Mesh) {
+        let (mut body_a, mut body_b) = match (a.body, b.body) {
+            (Some(body_a), Some(body_b)) => (body_a, body_b),
+            (Some(body_a), None) => (body_a, RigidBody::fixed()),
+            (None, Some(body_b)) => (RigidBody::fixed(), body_b),
+            (None, None) => return,
+        };
+        let total_mass = body_a.inverse_mass + body_b.inverse_mass;
+        if total_mass == num!(0.0) {
+            return;
+        }
+        let contact = match collision::deepest(&collision::collide(a, b)) {
+            Some(contact) => contact,
+            None => return,
+        };
+        let n = contact.normal;
+
+        // push them apart, the lighter one moves further
+        a.pos = a.pos - n * div(contact.depth * body_a.inverse_mass, total_mass);
+        b.pos = b.pos + n * div(contact.depth * body_b.inverse_mass, total_mass);
+
+        let radius_a = a.world_sphere().radius;
+        let radius_b = b.world_sphere().radius;
+        let ra = contact.point - a.pos;
+        let rb = contact.point - b.pos;
+        let v = body_a.velocity_at(ra) - body_b.velocity_at(rb);
+        let closing = v.dot(n);
+        if closing > num!(0.0) {
+            let bounce = if closing < self.settle_speed {
+                num!(0.0)
+            } else {
+                body_a.restitution.min(body_b.restitution)
+            };
+            let k = body_a.response(ra, n, radius_a) + body_b.response(rb, n, radius_b);
+            let j = div(closing * (num!(1.0) + bounce), k);
+            body_a.apply_impulse(ra, -n * j, radius_a);
+            body_b.apply_impulse(rb, n * j, radius_b);
+            let sliding = v - n * closing;
+            friction(
+                &mut body_a,
+                ra,
+                radius_a,
+                Some((&mut body_b, rb, radius_b)),
+                sliding,
+                j,
+            );
+        }
+
+        if a.body.is_some() {
+            a.body = Some(body_a);
+        }
+        if b.body.is_some() {
+            b.body = Some(body_b);
+        }
+    }
+}
+
+// slows the sliding at a contact, limited by how hard the two are pressed together
+fn friction(
+    a: &mut RigidBody,
+    ra: Vector3D<DefaultNum>,
+    radius_a: DefaultNum,
+    mut b: Option<(&mut RigidBody, Vector3D<DefaultNum>, DefaultNum)>,
+    sliding: Vector3D<DefaultNum>,
+    j: DefaultNum,
+) {
+    let speed = sliding.length();
+    if speed == num!(0.0) {
+        return;
+    }
+    let t = sliding.normalise();
+    let mut k = a.response(ra, t, radius_a);
+    let mut grip = a.friction;
+    if let Some((b, rb, radius_b)) = &b {
+        k += b.response(*rb, t, *radius_b);
+        grip = grip.min(b.friction);
+    }
+    if k == num!(0.0) {
+        return;
+    }
+    let jt = div(speed, k).min(grip * j);
+    a.apply_impulse(ra, -t * jt, radius_a);
+    if let Some((b, rb, radius_b)) = &mut b {
+        b.apply_impulse(*rb, t * jt, *radius_b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn falling_cube(restitution: DefaultNum) -> Mesh {
+        let mut mesh = cube();
+        let mut body = RigidBody::new(num!(1.0));
+        body.restitution = restitution;
+        mesh.body = Some(body);
+        mesh
+    }
+
+    #[test_case]
+    fn lands_on_ground(_gba: &mut agb::Gba) {
+        let mut physics = Physics::new();
+        physics.ground = Some(num!(100.0));
+        let mut meshes = vec![falling_cube(num!(0.0))];
+        for _ in 0..200 {
+            physics.step(&mut meshes);
+        }
+        let body = meshes[0].body.unwrap();
+        assert!((meshes[0].world_aabb().max.y - num!(100.0)).abs() < num!(0.1));
+        assert!(body.vel.y.abs() < physics.settle_speed);
+    }
+
+    // flat on, moving down at 4 a frame with the cube's bottom 6 above the ground, so it hits part way
+    // through the second frame
+    fn bounce(restitution: DefaultNum) -> DefaultNum {
+        let mut physics = Physics::new();
+        physics.ground = Some(num!(30.0));
+        let mut mesh = falling_cube(restitution);
+        let mut body = mesh.body.unwrap();
+        body.vel = Vector3D::new(num!(0.0), num!(4.0), num!(0.0));
+        body.gravity = Some(Vector3D::new(num!(0.0), num!(0.0), num!(0.0)));
+        mesh.body = Some(body);
+        let mut meshes = vec![mesh];
+        physics.step(&mut meshes);
+        physics.step(&mut meshes);
+        meshes[0].body.unwrap().vel.y
+    }
+
+    #[test_case]
+    fn bounces_with_restitution(_gba: &mut agb::Gba) {
+        assert_eq!(bounce(num!(1.0)), num!(-4.0));
+        assert_eq!(bounce(num!(0.5)), num!(-2.0));
+        assert_eq!(bounce(num!(0.0)), num!(0.0));
+    }
+
+    #[test_case]
+    fn gravity_override(_gba: &mut agb::Gba) {
+        let physics = Physics::new();
+        let mut balloon = falling_cube(num!(0.0));
+        balloon.pos.x = num!(200.0);
+        let mut body = balloon.body.unwrap();
+        body.gravity = Some(Vector3D::new(num!(0.0), num!(-0.1), num!(0.0)));
+        balloon.body = Some(body);
+        let mut meshes = vec![falling_cube(num!(0.0)), balloon];
+        physics.step(&mut meshes);
+        // a frame's worth, give or take rounding in the substeps
+        let close = |a: DefaultNum, b: DefaultNum| (a - b).abs() < num!(0.01);
+        assert!(close(meshes[0].body.unwrap().vel.y, physics.gravity.y));
+        assert!(close(meshes[1].body.unwrap().vel.y, num!(-0.1)));
+    }
+
+    #[test_case]
+    fn point_bodies_dont_spin(_gba: &mut agb::Gba) {
+        let mut body = RigidBody::new(num!(2.0));
+        let r = Vector3D::new(num!(1.0), num!(0.0), num!(0.0));
+        let n = Vector3D::new(num!(0.0), num!(1.0), num!(0.0));
+        assert_eq!(body.response(r, n, num!(0.0)), num!(0.5));
+        body.apply_impulse(r, n, num!(0.0));
+        assert_eq!(body.vel.y, num!(0.5));
+        assert_eq!(body.spin, Vector3D::new(num!(0.0), num!(0.0), num!(0.0)));
+    }
+}
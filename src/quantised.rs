@@ -0,0 +1,294 @@
+/*
+compact vertex storage for big models. positions are stored as i8 or i16 steps from a per mesh offset,
+so a static QuantisedMesh stays in rom and only the polygon currently being transformed gets expanded.
+include_quantised! embeds ones build.rs made from assets/scenes, so they never touch the heap.
+
+what fits:
+- positions: up to T::MAX steps either side of the middle of the bounds. the step is picked to fit the whole
+  mesh, so detail is the mesh's size over 254 for i8 and over 65534 for i16
+- normals: 127ths
+- texture coordinates: 256ths, from -128 up to just under 128. past that from_mesh gives an error instead of
+  wrapping, so tile big surfaces like terrain with smaller uvs
+*/
+
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use agb::fixnum::Vector2D;
+
+use crate::geometry::*;
+use crate::material::Material;
+use crate::matrix::*;
+use crate::quantised_raw;
+
+pub trait Quantised: Copy {
+    const MIN: i32;
+    const MAX: i32;
+    fn to_i32(self) -> i32;
+    // None when n doesn't fit
+    fn from_i32(n: i32) -> Option<Self>;
+}
+
+impl Quantised for i8 {
+    const MIN: i32 = i8::MIN as i32;
+    const MAX: i32 = i8::MAX as i32;
+    fn to_i32(self) -> i32 {
+        self as i32
+    }
+    fn from_i32(n: i32) -> Option<i8> {
+        i8::try_from(n).ok()
+    }
+}
+
+impl Quantised for i16 {
+    const MIN: i32 = i16::MIN as i32;
+    const MAX: i32 = i16::MAX as i32;
+    fn to_i32(self) -> i32 {
+        self as i32
+    }
+    fn from_i32(n: i32) -> Option<i16> {
+        i16::try_from(n).ok()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QuantisedPolygon<T: Quantised> {
+    pub material: u8,
+    pub verticies: [[T; 3]; 3],
+    // unit normal times 127
+    pub normal: [i8; 3],
+    // texture coordinates in 256ths, that's a texel each on the biggest textures
+    pub uv: [[i16; 2]; 3],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QuantiseError {
+    // this polygon has a corner that didn't fit in T
+    PositionOutOfRange(usize),
+    // this polygon has texture coordinates past -128 to 128
+    UvOutOfRange(usize),
+}
+
+pub struct QuantisedMesh<T: Quantised + 'static> {
+    pub polygons: Cow<'static, [QuantisedPolygon<T>]>,
+    pub materials: Cow<'static, [Material]>,
+    // a stored position is offset + value * step, step is a whole number of raw DefaultNum units
+    pub step: DefaultNum,
+    pub offset: Vector3D<DefaultNum>,
+    pub pos: Vector3D<DefaultNum>,
+    pub rot: Vector3D<DefaultNum>,
+    pub scale: Vector3D<DefaultNum>,
+}
+
+pub struct MemoryReport {
+    pub original: usize,
+    pub quantised: usize,
+}
+
+impl MemoryReport {
+    pub fn saved(&self) -> usize {
+        self.original.saturating_sub(self.quantised)
+    }
+}
+
+impl<T: Quantised + 'static> QuantisedMesh<T> {
+    // for data declared as a static in rom, nothing gets copied
+    pub const fn from_static(
+        polygons: &'static [QuantisedPolygon<T>],
+        materials: &'static [Material],
+        step: DefaultNum,
+        offset: Vector3D<DefaultNum>,
+    ) -> QuantisedMesh<T> {
+        QuantisedMesh {
+            polygons: Cow::Borrowed(polygons),
+            materials: Cow::Borrowed(materials),
+            step,
+            offset,
+            pos: Vector3D::new(
+                DefaultNum::from_raw(0),
+                DefaultNum::from_raw(0),
+                DefaultNum::from_raw(0),
+            ),
+            rot: Vector3D::new(
+                DefaultNum::from_raw(0),
+                DefaultNum::from_raw(0),
+                DefaultNum::from_raw(0),
+            ),
+            scale: Vector3D::new(
+                DefaultNum::from_raw(1 << 12),
+                DefaultNum::from_raw(1 << 12),
+                DefaultNum::from_raw(1 << 12),
+            ),
+        }
+    }
+
+    pub fn from_mesh(mesh: &Mesh) -> Result<QuantisedMesh<T>, QuantiseError> {
+        // center the steps on the bounding box so both signs of T get used
+        let (min, max) = (mesh.aabb.min, mesh.aabb.max);
+        let offset = Vector3D::new(
+            DefaultNum::from_raw(quantised_raw::center(min.x.to_raw(), max.x.to_raw())),
+            DefaultNum::from_raw(quantised_raw::center(min.y.to_raw(), max.y.to_raw())),
+            DefaultNum::from_raw(quantised_raw::center(min.z.to_raw(), max.z.to_raw())),
+        );
+        // the center rounds down, so the max side can reach a little further than the min side
+        let reach = max - offset;
+        let reach = reach.x.max(reach.y).max(reach.z);
+        let step = quantised_raw::step(reach.to_raw(), T::MAX);
+
+        let mut polygons: Vec<QuantisedPolygon<T>> = Vec::with_capacity(mesh.verticies.len());
+        for (i, poly) in mesh.verticies.iter().enumerate() {
+            let quantise = |n: DefaultNum, offset: DefaultNum| {
+                T::from_i32(quantised_raw::position(n.to_raw(), offset.to_raw(), step))
+                    .ok_or(QuantiseError::PositionOutOfRange(i))
+            };
+            let corner = |v: Vector3D<DefaultNum>| -> Result<[T; 3], QuantiseError> {
+                Ok([
+                    quantise(v.x, offset.x)?,
+                    quantise(v.y, offset.y)?,
+                    quantise(v.z, offset.z)?,
+                ])
+            };
+            let uv = |uv: Vector2D<DefaultNum>| -> Result<[i16; 2], QuantiseError> {
+                let n = |n: DefaultNum| {
+                    quantised_raw::uv(n.to_raw()).ok_or(QuantiseError::UvOutOfRange(i))
+                };
+                Ok([n(uv.x)?, n(uv.y)?])
+            };
+            let [a, b, c] = poly.verticies;
+            let [ua, ub, uc] = poly.uv;
+            polygons.push(QuantisedPolygon {
+                material: poly.material,
+                verticies: [corner(a)?, corner(b)?, corner(c)?],
+                normal: [poly.normal.x, poly.normal.y, poly.normal.z]
+                    .map(|n| quantised_raw::normal(n.to_raw())),
+                uv: [uv(ua)?, uv(ub)?, uv(uc)?],
+            });
+        }
+
+        Ok(QuantisedMesh {
+            polygons: Cow::Owned(polygons),
+            materials: mesh.materials.clone(),
+            step: DefaultNum::from_raw(step),
+            offset,
+            pos: mesh.pos,
+            rot: mesh.rot,
+            scale: mesh.scale,
+        })
+    }
+
+    pub fn expand(&self, poly: &QuantisedPolygon<T>) -> Polygon {
+        let step = self.step.to_raw();
+        let position = |v: [T; 3]| {
+            Vector3D::new(
+                self.offset.x + DefaultNum::from_raw(v[0].to_i32() * step),
+                self.offset.y + DefaultNum::from_raw(v[1].to_i32() * step),
+                self.offset.z + DefaultNum::from_raw(v[2].to_i32() * step),
+            )
+        };
+        let normal = |n: i8| DefaultNum::from_raw(((n as i32) << 12) / 127);
+        Polygon {
+            material: poly.material,
+            verticies: poly.verticies.map(position),
+            normal: Vector3D::new(
+                normal(poly.normal[0]),
+                normal(poly.normal[1]),
+                normal(poly.normal[2]),
+            ),
+            uv: poly.uv.map(|uv| {
+                Vector2D::new(
+                    DefaultNum::from_raw((uv[0] as i32) << 4),
+                    DefaultNum::from_raw((uv[1] as i32) << 4),
+                )
+            }),
+        }
+    }
+
+    // how much smaller this is than the mesh it was made from
+    pub fn memory(&self) -> MemoryReport {
+        MemoryReport {
+            original: self.polygons.len() * size_of::<Polygon>(),
+            quantised: self.polygons.len() * size_of::<QuantisedPolygon<T>>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agb::fixnum::num;
+
+    // the cube with some texture coordinates, up near the edge of what fits
+    fn textured_cube() -> Mesh {
+        let mut polygons = cube().verticies.to_vec();
+        for (i, poly) in polygons.iter_mut().enumerate() {
+            let n = DefaultNum::new(i as i32 * 10) - num!(0.3);
+            poly.uv = [
+                Vector2D::new(n, -n),
+                Vector2D::new(num!(127.5), num!(-128.0)),
+                Vector2D::new(num!(0.25), n / 2),
+            ];
+        }
+        let mut mesh = Mesh::new(polygons, cube().pos, cube().rot);
+        mesh.materials = cube().materials;
+        mesh
+    }
+
+    fn round_trip<T: Quantised>(mesh: &Mesh) {
+        let quantised = QuantisedMesh::<T>::from_mesh(mesh).unwrap();
+        let close = |a: DefaultNum, b: DefaultNum, tolerance: i32| {
+            assert!((a - b).to_raw().abs() <= tolerance, "{:?} and {:?}", a, b);
+        };
+        let half_step = quantised.step.to_raw() / 2;
+        for (poly, q) in mesh.verticies.iter().zip(quantised.polygons.iter()) {
+            let expanded = quantised.expand(q);
+            assert_eq!(expanded.material, poly.material);
+            for (a, b) in expanded.verticies.iter().zip(poly.verticies) {
+                close(a.x, b.x, half_step);
+                close(a.y, b.y, half_step);
+                close(a.z, b.z, half_step);
+            }
+            // two 127ths, one lost each way
+            close(expanded.normal.x, poly.normal.x, 65);
+            close(expanded.normal.y, poly.normal.y, 65);
+            close(expanded.normal.z, poly.normal.z, 65);
+            for (a, b) in expanded.uv.iter().zip(poly.uv) {
+                close(a.x, b.x, 16);
+                close(a.y, b.y, 16);
+            }
+        }
+    }
+
+    #[test_case]
+    fn round_trip_i8(_gba: &mut agb::Gba) {
+        round_trip::<i8>(&textured_cube());
+    }
+
+    #[test_case]
+    fn round_trip_i16(_gba: &mut agb::Gba) {
+        round_trip::<i16>(&textured_cube());
+    }
+
+    #[test_case]
+    fn uv_out_of_range(_gba: &mut agb::Gba) {
+        let mut polygons = cube().verticies.to_vec();
+        polygons[5].uv[2] = Vector2D::new(num!(128.0), num!(0.0));
+        let mesh = Mesh::new(polygons, cube().pos, cube().rot);
+        assert_eq!(
+            QuantisedMesh::<i16>::from_mesh(&mesh).err(),
+            Some(QuantiseError::UvOutOfRange(5))
+        );
+    }
+
+    #[test_case]
+    fn build_matches_from_mesh(_gba: &mut agb::Gba) {
+        let scene = include_scene!("cube").unwrap();
+        let built = &include_quantised!("cube")[0];
+        let converted = QuantisedMesh::<i16>::from_mesh(&scene.meshes[0]).unwrap();
+        assert_eq!(built.polygons, converted.polygons);
+        assert_eq!(built.step, converted.step);
+        assert_eq!(built.offset, converted.offset);
+        assert_eq!(built.pos, converted.pos);
+    }
+}
@@ -0,0 +1,31 @@
+/*
+the arithmetic behind quantised.rs, done on raw DefaultNum values (i32s with 12 fractional bits). build.rs pulls
+this file in with #[path] so the models it quantises come out exactly as QuantisedMesh::from_mesh makes them,
+so it can only use core.
+*/
+
+// the middle of one axis of a bounding box, rounded the way Aabb::center does it
+pub fn center(min: i32, max: i32) -> i32 {
+    (min + max) >> 1
+}
+
+// the smallest step that fits every point within `reach` of the center into -max..=max steps
+pub fn step(reach: i32, max: i32) -> i32 {
+    ((reach + max - 1) / max).max(1)
+}
+
+// round to the nearest step rather than towards zero
+pub fn position(n: i32, center: i32, step: i32) -> i32 {
+    let steps = n - center;
+    (steps + steps.signum() * step / 2) / step
+}
+
+// unit normal times 127
+pub fn normal(n: i32) -> i8 {
+    ((n * 127) >> 12).clamp(-127, 127) as i8
+}
+
+// texture coordinates in 256ths, None past the -128 to 128 they can reach
+pub fn uv(n: i32) -> Option<i16> {
+    i16::try_from(n >> 4).ok()
+}
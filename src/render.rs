@@ -0,0 +1,337 @@
+use agb::fixnum::{num, Vector2D};
+
+use crate::backend::Backend;
+use crate::bounds::BoundingSphere;
+use crate::drawing::*;
+use crate::geometry::*;
+use crate::lod::LodMetric;
+use crate::material::*;
+use crate::matrix::*;
+use crate::particles::{Emitter, ParticleShape};
+use crate::quantised::{Quantised, QuantisedMesh};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    // polygons, with any lines drawn on top
+    Filled,
+    // only the mesh's lines, polygons are skipped completely
+    Wireframe,
+}
+
+pub struct Renderer {
+    // which bitmap mode to draw into, pass it to Screen::new
+    pub backend: Backend,
+    // size of the area being drawn to, the projection is centered in it
+    pub width: i32,
+    pub height: i32,
+    pub focal_length: DefaultNum,
+    // squashes the projection on each axis, for backends the hardware stretches back up to the full screen
+    pub pixel_scale: Vector2D<DefaultNum>,
+    // anything closer to the camera than this gets dropped instead of projected
+    pub near_plane: DefaultNum,
+    // color to draw polygon edges in on top of the fill
+    pub outline: Option<u8>,
+    pub mode: RenderMode,
+    // unit length, the way the light is shining. lights Flat and Lit materials
+    pub light: Vector3D<DefaultNum>,
+    // how bright faces turned away from the light still are, 0 to 1
+    pub ambient: DefaultNum,
+    // Lit materials are fully dark this far from the camera
+    pub fog_distance: DefaultNum,
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer::with_backend(Backend::Mode4)
+    }
+
+    pub fn with_backend(backend: Backend) -> Renderer {
+        let (width, height) = backend.size();
+        Renderer {
+            backend,
+            width,
+            height,
+            focal_length: num!(128.0),
+            pixel_scale: backend.pixel_scale(),
+            near_plane: num!(8.0),
+            outline: None,
+            mode: RenderMode::Filled,
+            // down, forwards and a bit to the right
+            light: Vector3D::new(num!(0.33), num!(0.67), num!(0.67)),
+            ambient: num!(0.25),
+            fog_distance: num!(512.0),
+        }
+    }
+
+    pub fn draw_mesh<D: Draw>(&self, target: &mut D, camera: &Camera, mesh: &Mesh) {
+        if self.mode == RenderMode::Filled {
+            self.draw_mesh_polygons(target, camera, mesh);
+        }
+        self.draw_lines(target, camera, mesh);
+    }
+
+    fn draw_mesh_polygons<D: Draw>(&self, target: &mut D, camera: &Camera, mesh: &Mesh) {
+        let level = match &mesh.lod {
+            Some(lod) => {
+                // every level is the same object, so the mesh's own bounds stand in for all of them
+                let sphere = mesh.world_sphere();
+                lod.select(match lod.metric {
+                    LodMetric::ScreenSize => self.projected_radius(camera, &sphere),
+                    LodMetric::Distance => (sphere.center - camera.pos).length(),
+                })
+            }
+            None => 0,
+        };
+        if level > 0 {
+            self.draw_polygons(
+                target,
+                camera,
+                mesh.transform(),
+                &mesh.materials,
+                mesh.level(level).iter().copied(),
+            );
+            return;
+        }
+
+        let morphing = mesh.is_morphing();
+        // bone matrices only need working out once for the whole mesh
+        let skin = mesh.skin.as_ref().map(|skin| skin.skeleton.skin_matrices());
+        self.draw_polygons(
+            target,
+            camera,
+            mesh.transform(),
+            &mesh.materials,
+            (0..mesh.verticies.len()).map(|i| {
+                let poly = if morphing {
+                    mesh.morphed(i)
+                } else {
+                    mesh.verticies[i]
+                };
+                match &skin {
+                    Some(matrices) => mesh.skinned(poly, i, matrices),
+                    None => poly,
+                }
+            }),
+        );
+    }
+
+    // positions are only expanded to DefaultNum as each polygon comes through the transform
+    pub fn draw_quantised<D: Draw, T: Quantised>(
+        &self,
+        target: &mut D,
+        camera: &Camera,
+        mesh: &QuantisedMesh<T>,
+    ) {
+        self.draw_polygons(
+            target,
+            camera,
+            Transform {
+                pos: mesh.pos,
+                rot: mesh.rot,
+                scale: mesh.scale,
+            },
+            &mesh.materials,
+            mesh.polygons.iter().map(|poly| mesh.expand(poly)),
+        );
+    }
+
+    fn draw_polygons<D: Draw>(
+        &self,
+        target: &mut D,
+        camera: &Camera,
+        transform: Transform,
+        materials: &[Material],
+        polygons: impl Iterator<Item = Polygon>,
+    ) {
+        let Transform { pos, rot, scale } = transform;
+        let model_matrix = Matrix::model(rot, scale);
+        let rotation = Matrix::rotation(rot);
+        let view_matrix = Matrix::rotation(camera.rot).transpose();
+        // culling happens in object space, so faces that point away are never transformed
+        let eye = to_object_space(camera.pos, pos, rot, scale);
+        let to_view = |v: Vector3D<DefaultNum>| {
+            view_matrix.transform(model_matrix.transform(v) + pos - camera.pos)
+        };
+
+        for poly in polygons {
+            let material = materials.get(poly.material as usize).unwrap_or(&MISSING);
+            if !poly.is_visible(eye, material.cull) {
+                continue;
+            }
+
+            let transformed_tri: Triangle3D<DefaultNum> = [
+                to_view(poly.verticies[0]),
+                to_view(poly.verticies[1]),
+                to_view(poly.verticies[2]),
+            ];
+
+            let flat_tri = match self.to_triangle2D(&transformed_tri) {
+                Some(tri) => tri,
+                None => continue,
+            };
+
+            let brightness = match material.shading {
+                Shading::Unlit | Shading::Wireframe => num!(1.0),
+                Shading::Flat => self.brightness(rotation.transform(poly.normal)),
+                Shading::Lit => {
+                    let depth =
+                        (transformed_tri[0].z + transformed_tri[1].z + transformed_tri[2].z) / 3;
+                    let fade =
+                        num!(1.0) - div(depth, self.fog_distance).clamp(num!(0.0), num!(1.0));
+                    self.brightness(rotation.transform(poly.normal)) * fade
+                }
+            };
+            let color = material.color(brightness);
+            let z = [
+                transformed_tri[0].z,
+                transformed_tri[1].z,
+                transformed_tri[2].z,
+            ];
+            target.depth_hint(z);
+            if material.shading == Shading::Wireframe {
+                draw_edges(target, &flat_tri, z, color);
+                continue;
+            }
+
+            match material.rgb_color(brightness) {
+                Some(rgb) => target.draw_tri_rgb(&flat_tri, z, rgb, color),
+                None => target.draw_tri_depth(&flat_tri, z, color),
+            }
+            if let Some(color) = self.outline {
+                draw_edges(target, &flat_tri, z, color);
+            }
+        }
+    }
+
+    // 0 to 1 for a world space normal, faces turned away from the light still get the ambient
+    fn brightness(&self, normal: Vector3D<DefaultNum>) -> DefaultNum {
+        let facing = (-normal.dot(self.light)).max(num!(0.0));
+        self.ambient + (num!(1.0) - self.ambient) * facing
+    }
+
+    // lines aren't morphed or skinned, they follow the mesh's transform only
+    fn draw_lines<D: Draw>(&self, target: &mut D, camera: &Camera, mesh: &Mesh) {
+        if mesh.lines.is_empty() {
+            return;
+        }
+        let model_matrix = mesh.model_matrix();
+        let view_matrix = Matrix::rotation(camera.rot).transpose();
+        let to_view = |v: Vector3D<DefaultNum>| {
+            view_matrix.transform(model_matrix.transform(v) + mesh.pos - camera.pos)
+        };
+
+        for line in mesh.lines.iter() {
+            let a = to_view(line.verticies[0]);
+            let b = to_view(line.verticies[1]);
+            if let Some((a, b)) = self.clip_line(a, b) {
+                target.depth_hint([a.z, b.z, (a.z + b.z) / 2]);
+                target.draw_line_depth(self.project(a), self.project(b), [a.z, b.z], line.color);
+            }
+        }
+    }
+
+    // cuts off the part of a view space line behind the near plane, unlike polygons which get dropped
+    fn clip_line(
+        &self,
+        a: Vector3D<DefaultNum>,
+        b: Vector3D<DefaultNum>,
+    ) -> Option<(Vector3D<DefaultNum>, Vector3D<DefaultNum>)> {
+        let near = self.near_plane;
+        match (a.z < near, b.z < near) {
+            (true, true) => None,
+            (false, false) => Some((a, b)),
+            (a_behind, _) => {
+                let (behind, front) = if a_behind { (a, b) } else { (b, a) };
+                let t = div(near - behind.z, front.z - behind.z);
+                let clipped = behind + (front - behind) * t;
+                Some(if a_behind {
+                    (clipped, front)
+                } else {
+                    (front, clipped)
+                })
+            }
+        }
+    }
+
+    pub fn draw_particles<D: Draw>(&self, target: &mut D, camera: &Camera, emitter: &Emitter) {
+        let view_matrix = Matrix::rotation(camera.rot).transpose();
+        for particle in &emitter.particles {
+            let pos = view_matrix.transform(particle.pos - camera.pos);
+            if pos.z < self.near_plane {
+                continue;
+            }
+            let center = self.project(pos);
+            let color = emitter.color(particle);
+            target.depth_hint([pos.z; 3]);
+            let half = match emitter.shape {
+                ParticleShape::Point => num!(0.0),
+                ParticleShape::Quad(size) => size * div(self.focal_length, pos.z) / 2,
+            };
+            // quads smaller than a pixel may as well be points
+            if half < num!(0.5) {
+                target.draw_dot_depth(center, pos.z, color);
+                continue;
+            }
+            let corners = [
+                center + Vector2D::new(-half, -half),
+                center + Vector2D::new(half, -half),
+                center + Vector2D::new(half, half),
+                center + Vector2D::new(-half, half),
+            ];
+            // quads face the camera, so the whole thing is as far away as its center
+            target.draw_tri_depth(&[corners[0], corners[1], corners[2]], [pos.z; 3], color);
+            target.draw_tri_depth(&[corners[0], corners[2], corners[3]], [pos.z; 3], color);
+        }
+    }
+
+    pub fn to_view_space(
+        &self,
+        camera: &Camera,
+        world: Vector3D<DefaultNum>,
+    ) -> Vector3D<DefaultNum> {
+        Matrix::rotation(camera.rot)
+            .transpose()
+            .transform(world - camera.pos)
+    }
+
+    // roughly how many pixels across the radius of a world space sphere ends up on screen
+    pub fn projected_radius(&self, camera: &Camera, sphere: &BoundingSphere) -> DefaultNum {
+        let depth = self.to_view_space(camera, sphere.center).z;
+        if depth < self.near_plane {
+            // the camera is inside or right up against it, so it's as big as it gets
+            return DefaultNum::new(self.width);
+        }
+        sphere.radius * div(self.focal_length, depth)
+    }
+
+    fn to_triangle2D(&self, tri: &Triangle3D<DefaultNum>) -> Option<Triangle2D<DefaultNum>> {
+        if tri.iter().any(|v| v.z < self.near_plane) {
+            return None;
+        }
+        Some([
+            self.project(tri[0]),
+            self.project(tri[1]),
+            self.project(tri[2]),
+        ])
+    }
+
+    pub fn project(&self, v: Vector3D<DefaultNum>) -> Vector2D<DefaultNum> {
+        let scale = div(self.focal_length, v.z);
+        Vector2D::new(
+            v.x * scale * self.pixel_scale.x + self.width / 2,
+            v.y * scale * self.pixel_scale.y + self.height / 2,
+        )
+    }
+}
+
+// wireframes and outlines, each edge at the depth of the corners it joins
+fn draw_edges<D: Draw>(
+    target: &mut D,
+    tri: &Triangle2D<DefaultNum>,
+    z: [DefaultNum; 3],
+    color: u8,
+) {
+    for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+        target.draw_line_depth(tri[a], tri[b], [z[a], z[b]], color);
+    }
+}
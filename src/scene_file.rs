@@ -0,0 +1,426 @@
+/*
+binary scenes, so levels can be swapped out without touching any code. embed one with include_scene!. the layout
+itself is in scene_format.rs, this turns it into meshes and materials and back.
+
+scenes embedded with include_scene! come from build.rs, which turns obj models in assets/scenes into this
+format on the host with the same scene_format.rs. from_static reads those straight out of rom and borrows the
+material ramps rather than copying them. polygons can't be borrowed, the file packs them differently to
+Polygon, so they're decoded once at load. textures live in rom and aren't saved. use mesh! instead for a model
+that has to stay entirely in rom. write is the console side writer, for saving scenes that were built or
+edited at runtime.
+*/
+
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use agb::fixnum::Vector2D;
+
+use crate::backend::Rgb15;
+use crate::geometry::*;
+use crate::material::{Material, Shading};
+use crate::matrix::*;
+use crate::scene_format::*;
+use crate::validate::{validate, MeshIssue};
+
+const ZERO: DefaultNum = DefaultNum::from_raw(0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SceneError {
+    BadMagic,
+    // the major version, made by a newer or older writer
+    UnsupportedVersion(u8),
+    // ran out of data part way through, the file is cut short
+    Truncated,
+    BadChecksum([u8; 4]),
+    MissingSection([u8; 4]),
+    // an object points at a mesh that isn't in the file
+    BadMeshIndex(u16),
+    BadCullMode(u8),
+    BadShading(u8),
+    // a MATL or UVCO section with no MESH before it, or an MRGB with no MATL
+    StraySection([u8; 4]),
+    // a mesh that would draw wrong, see validate.rs
+    BadMesh(MeshIssue),
+    // write only, a count in this section doesn't fit in the field it's saved in
+    TooBig([u8; 4]),
+}
+
+impl From<FormatError> for SceneError {
+    fn from(error: FormatError) -> SceneError {
+        match error {
+            FormatError::BadMagic => SceneError::BadMagic,
+            FormatError::UnsupportedVersion(major) => SceneError::UnsupportedVersion(major),
+            FormatError::Truncated => SceneError::Truncated,
+            FormatError::BadChecksum(tag) => SceneError::BadChecksum(tag),
+            FormatError::MissingSection(tag) => SceneError::MissingSection(tag),
+            FormatError::StraySection(tag) => SceneError::StraySection(tag),
+            FormatError::TooBig(tag) => SceneError::TooBig(tag),
+        }
+    }
+}
+
+fn vector(v: RawVector) -> Vector3D<DefaultNum> {
+    Vector3D::new(
+        DefaultNum::from_raw(v[0]),
+        DefaultNum::from_raw(v[1]),
+        DefaultNum::from_raw(v[2]),
+    )
+}
+
+fn raw(v: Vector3D<DefaultNum>) -> RawVector {
+    [v.x.to_raw(), v.y.to_raw(), v.z.to_raw()]
+}
+
+fn read_mesh<'a>(
+    raw: &RawMesh<'a>,
+    ramp: fn(&'a [u8]) -> Cow<'static, [u8]>,
+) -> Result<Mesh, SceneError> {
+    let mut polygons: Vec<Polygon> = raw
+        .polygons
+        .iter()
+        .map(|poly| {
+            Polygon::from_parts(
+                poly.material,
+                poly.verticies.map(vector),
+                vector(poly.normal),
+            )
+        })
+        .collect();
+    if let Some(uvs) = &raw.uvs {
+        for (poly, corners) in polygons.iter_mut().zip(uvs) {
+            poly.uv = corners
+                .map(|[u, v]| Vector2D::new(DefaultNum::from_raw(u), DefaultNum::from_raw(v)));
+        }
+    }
+    // open and non-manifold edges are let through, a level can have a flat floor in it
+    if let Some(issue) = validate(&polygons).into_iter().find(MeshIssue::is_error) {
+        return Err(SceneError::BadMesh(issue));
+    }
+
+    let mut materials = Vec::with_capacity(raw.materials.len());
+    for (i, material) in raw.materials.iter().enumerate() {
+        let shading = match material.shading {
+            SHADING_UNLIT => Shading::Unlit,
+            SHADING_FLAT => Shading::Flat,
+            SHADING_LIT => Shading::Lit,
+            SHADING_WIREFRAME => Shading::Wireframe,
+            other => return Err(SceneError::BadShading(other)),
+        };
+        let cull = match material.cull {
+            CULL_BACK => CullMode::Back,
+            CULL_FRONT => CullMode::Front,
+            CULL_NONE => CullMode::None,
+            other => return Err(SceneError::BadCullMode(other)),
+        };
+        let rgb = match raw.rgbs.as_ref().map(|rgbs| rgbs[i]) {
+            None | Some(NO_RGB) => None,
+            Some(rgb) => Some(Rgb15(rgb)),
+        };
+        materials.push(Material {
+            ramp: ramp(material.ramp),
+            shading,
+            cull,
+            texture: None,
+            rgb,
+        });
+    }
+
+    let zero = Vector3D::new(ZERO, ZERO, ZERO);
+    let mut mesh = Mesh::new(polygons, zero, zero);
+    mesh.lines = raw
+        .lines
+        .iter()
+        .map(|line| Line {
+            color: line.color,
+            verticies: line.verticies.map(vector),
+        })
+        .collect::<Vec<Line>>()
+        .into();
+    mesh.materials = materials.into();
+    mesh.recalculate_bounds();
+    Ok(mesh)
+}
+
+impl Scene {
+    // copies everything it needs, so data can be dropped afterwards
+    pub fn from_bytes(data: &[u8]) -> Result<Scene, SceneError> {
+        Scene::read(data, |ramp| Cow::Owned(ramp.to_vec()))
+    }
+
+    // for files in rom, the material ramps point into data instead of being copied
+    pub fn from_static(data: &'static [u8]) -> Result<Scene, SceneError> {
+        Scene::read(data, Cow::Borrowed)
+    }
+
+    fn read<'a>(
+        data: &'a [u8],
+        ramp: fn(&'a [u8]) -> Cow<'static, [u8]>,
+    ) -> Result<Scene, SceneError> {
+        let raw = RawScene::read(data)?;
+        let mut meshes = Vec::with_capacity(raw.meshes.len());
+        for mesh in raw.meshes.iter() {
+            meshes.push(Some(read_mesh(mesh, ramp)?));
+        }
+
+        // the first object to use a mesh takes it, any after that get their own copy of the polygons
+        let mut owners: Vec<Option<usize>> = meshes.iter().map(|_| None).collect();
+        let mut placed: Vec<Mesh> = Vec::with_capacity(raw.objects.len());
+        for object in raw.objects.iter() {
+            let index = object.mesh;
+            let slot = meshes
+                .get_mut(index as usize)
+                .ok_or(SceneError::BadMeshIndex(index))?;
+            let mut mesh = match (slot.take(), owners[index as usize]) {
+                (Some(mesh), _) => mesh,
+                (None, Some(owner)) => {
+                    let original = &placed[owner];
+                    let zero = Vector3D::new(ZERO, ZERO, ZERO);
+                    let mut copy = Mesh::new(original.verticies.clone(), zero, zero);
+                    copy.lines = original.lines.clone();
+                    copy.materials = original.materials.clone();
+                    copy.recalculate_bounds();
+                    copy
+                }
+                (None, None) => return Err(SceneError::BadMeshIndex(index)),
+            };
+            owners[index as usize].get_or_insert(placed.len());
+            mesh.pos = vector(object.pos);
+            mesh.rot = vector(object.rot);
+            mesh.scale = vector(object.scale);
+            placed.push(mesh);
+        }
+
+        Ok(Scene {
+            camera: Camera {
+                pos: vector(raw.camera_pos),
+                rot: vector(raw.camera_rot),
+            },
+            meshes: placed,
+        })
+    }
+}
+
+fn write_mesh(mesh: &Mesh) -> RawMesh<'_> {
+    let zero = Vector2D::new(ZERO, ZERO);
+    let textured = mesh.verticies.iter().any(|poly| poly.uv != [zero; 3]);
+    let colored = mesh.materials.iter().any(|material| material.rgb.is_some());
+    RawMesh {
+        polygons: mesh
+            .verticies
+            .iter()
+            .map(|poly| RawPolygon {
+                material: poly.material,
+                verticies: poly.verticies.map(raw),
+                normal: raw(poly.normal),
+            })
+            .collect(),
+        lines: mesh
+            .lines
+            .iter()
+            .map(|line| RawLine {
+                color: line.color,
+                verticies: line.verticies.map(raw),
+            })
+            .collect(),
+        uvs: textured.then(|| {
+            mesh.verticies
+                .iter()
+                .map(|poly| poly.uv.map(|uv| [uv.x.to_raw(), uv.y.to_raw()]))
+                .collect()
+        }),
+        materials: mesh
+            .materials
+            .iter()
+            .map(|material| RawMaterial {
+                shading: match material.shading {
+                    Shading::Unlit => SHADING_UNLIT,
+                    Shading::Flat => SHADING_FLAT,
+                    Shading::Lit => SHADING_LIT,
+                    Shading::Wireframe => SHADING_WIREFRAME,
+                },
+                cull: match material.cull {
+                    CullMode::Back => CULL_BACK,
+                    CullMode::Front => CULL_FRONT,
+                    CullMode::None => CULL_NONE,
+                },
+                ramp: &material.ramp,
+            })
+            .collect(),
+        rgbs: colored.then(|| {
+            mesh.materials
+                .iter()
+                .map(|material| material.rgb.map_or(NO_RGB, |rgb| rgb.0))
+                .collect()
+        }),
+    }
+}
+
+// the whole scene as a file from_bytes can read back, each object gets its own MESH section
+pub fn write(scene: &Scene) -> Result<Vec<u8>, SceneError> {
+    let file = RawScene {
+        camera_pos: raw(scene.camera.pos),
+        camera_rot: raw(scene.camera.rot),
+        meshes: scene.meshes.iter().map(write_mesh).collect(),
+        objects: scene
+            .meshes
+            .iter()
+            .enumerate()
+            .map(|(i, mesh)| {
+                Ok(RawObject {
+                    mesh: u16::try_from(i).map_err(|_| SceneError::TooBig(*b"OBJS"))?,
+                    pos: raw(mesh.pos),
+                    rot: raw(mesh.rot),
+                    scale: raw(mesh.scale),
+                })
+            })
+            .collect::<Result<Vec<RawObject>, SceneError>>()?,
+    };
+    Ok(file.write()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agb::fixnum::num;
+    use alloc::vec;
+
+    fn scene() -> Scene {
+        let mut mesh = cube();
+        mesh.pos = Vector3D::new(num!(1.5), num!(-2.0), num!(32.0));
+        mesh.rot = Vector3D::new(num!(0.25), num!(0.0), num!(0.125));
+        mesh.scale = Vector3D::new(num!(2.0), num!(1.0), num!(0.5));
+        let mut polygons = mesh.verticies.to_vec();
+        polygons[0].uv[1] = Vector2D::new(num!(1.0), num!(0.5));
+        mesh.verticies = polygons.into();
+        let mut materials = mesh.materials.to_vec();
+        materials[1] = materials[1].clone().with_rgb(Rgb15::new(31, 16, 4));
+        materials[2].cull = CullMode::None;
+        mesh.materials = materials.into();
+        Scene {
+            camera: Camera {
+                pos: Vector3D::new(num!(0.0), num!(-10.0), num!(-160.0)),
+                rot: Vector3D::new(num!(0.0), num!(0.5), num!(0.0)),
+            },
+            meshes: vec![mesh],
+        }
+    }
+
+    #[test_case]
+    fn round_trip(_gba: &mut agb::Gba) {
+        let original = scene();
+        let data = write(&original).unwrap();
+        let loaded = Scene::from_bytes(&data).unwrap();
+
+        assert_eq!(loaded.camera.pos, original.camera.pos);
+        assert_eq!(loaded.camera.rot, original.camera.rot);
+        assert_eq!(loaded.meshes.len(), 1);
+        let (a, b) = (&original.meshes[0], &loaded.meshes[0]);
+        assert_eq!((a.pos, a.rot, a.scale), (b.pos, b.rot, b.scale));
+        assert_eq!(a.verticies.len(), b.verticies.len());
+        for (p, q) in a.verticies.iter().zip(b.verticies.iter()) {
+            assert_eq!(p.verticies, q.verticies);
+            assert_eq!(p.normal, q.normal);
+            assert_eq!(p.uv, q.uv);
+            assert_eq!(p.material, q.material);
+        }
+        for (m, n) in a.materials.iter().zip(b.materials.iter()) {
+            assert_eq!(m.ramp, n.ramp);
+            assert_eq!(m.shading, n.shading);
+            assert_eq!(m.cull, n.cull);
+            assert_eq!(m.rgb, n.rgb);
+        }
+        // and writing it again gives exactly the same file
+        assert_eq!(write(&loaded).unwrap(), data);
+    }
+
+    #[test_case]
+    fn truncated(_gba: &mut agb::Gba) {
+        let data = write(&scene()).unwrap();
+        for cut in 0..data.len() {
+            let expected = if cut < 4 {
+                SceneError::BadMagic
+            } else {
+                SceneError::Truncated
+            };
+            assert_eq!(Scene::from_bytes(&data[..cut]).err(), Some(expected));
+        }
+    }
+
+    #[test_case]
+    fn huge_section_length(_gba: &mut agb::Gba) {
+        let mut data = vec![];
+        data.extend_from_slice(&MAGIC);
+        data.extend_from_slice(&[MAJOR_VERSION, MINOR_VERSION, 1, 0]);
+        data.extend_from_slice(b"MESH");
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(Scene::from_bytes(&data).err(), Some(SceneError::Truncated));
+    }
+
+    #[test_case]
+    fn bad_checksum(_gba: &mut agb::Gba) {
+        let mut data = write(&scene()).unwrap();
+        // the last byte is part of the OBJS payload
+        *data.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            Scene::from_bytes(&data).err(),
+            Some(SceneError::BadChecksum(*b"OBJS"))
+        );
+    }
+
+    #[test_case]
+    fn bad_winding(_gba: &mut agb::Gba) {
+        let mut scene = scene();
+        let mut polygons = scene.meshes[0].verticies.to_vec();
+        polygons[3].verticies.swap(1, 2);
+        scene.meshes[0].verticies = polygons.into();
+        let data = write(&scene).unwrap();
+        assert!(matches!(
+            Scene::from_bytes(&data),
+            Err(SceneError::BadMesh(MeshIssue::InconsistentWinding(_, _)))
+        ));
+    }
+
+    #[test_case]
+    fn versions(_gba: &mut agb::Gba) {
+        let mut data = write(&scene()).unwrap();
+        data[5] = MINOR_VERSION + 1;
+        assert!(Scene::from_bytes(&data).is_ok());
+        data[4] = MAJOR_VERSION + 1;
+        assert_eq!(
+            Scene::from_bytes(&data).err(),
+            Some(SceneError::UnsupportedVersion(MAJOR_VERSION + 1))
+        );
+    }
+
+    #[test_case]
+    fn too_big(_gba: &mut agb::Gba) {
+        let mut scene = scene();
+        scene.meshes[0].materials = vec![Material {
+            ramp: vec![1; 256].into(),
+            ..Material::solid(&[])
+        }]
+        .into();
+        assert_eq!(write(&scene).err(), Some(SceneError::TooBig(*b"MATL")));
+    }
+
+    // assets/scenes/cube.obj goes through build.rs's writer, so this checks the host side against this reader
+    #[test_case]
+    fn host_written(_gba: &mut agb::Gba) {
+        let scene = include_scene!("cube").unwrap();
+        let cube = cube();
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.verticies.len(), cube.verticies.len());
+        for (p, q) in mesh.verticies.iter().zip(cube.verticies.iter()) {
+            assert_eq!(p.verticies, q.verticies);
+            assert_eq!(p.normal, q.normal);
+            assert_eq!(p.material, q.material);
+        }
+        for (m, n) in mesh.materials.iter().zip(cube.materials.iter()) {
+            assert_eq!(m.ramp, n.ramp);
+            // read straight out of rom
+            assert!(matches!(m.ramp, Cow::Borrowed(_)));
+        }
+    }
+}
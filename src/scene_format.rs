@@ -0,0 +1,413 @@
+/*
+the layout of a scene file, on raw numbers rather than engine types so build.rs can pull this file in with
+#[path] and write scenes on the host with exactly the code the console reads them with. it can only use core
+and alloc. scene_file.rs turns what's in here into meshes and back.
+
+everything is little endian and numbers are raw DefaultNums (i32, 12 fractional bits).
+
+    header:  "G3DS", major version u8, minor version u8, section count u16
+    section: tag [u8; 4], length u32, adler32 of the payload u32, then the payload
+
+    CAMR  pos, rot
+    MESH  polygon count u16, line count u16,
+          polygons (material u8, 3 verticies, normal), lines (color u8, 2 verticies)
+    UVCO  per polygon of the MESH before it: 3 uvs of 2 numbers
+    MATL  count u16, then per material: shading u8, cull u8, ramp length u8, ramp
+    MRGB  per material of the MATL before it: rgb u16, 0xffff for none
+    OBJS  count u16, then per object: mesh u16, pos, rot, scale
+
+MESH sections are numbered in the order they appear and objects point at them. MATL and UVCO sections belong
+to the MESH just before them and MRGB to the MATL. sections with tags this version doesn't know about are
+skipped, so files from a newer minor version still load. a different major version means the sections
+themselves changed and the file is turned away.
+*/
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+pub const MAGIC: [u8; 4] = *b"G3DS";
+pub const MAJOR_VERSION: u8 = 2;
+pub const MINOR_VERSION: u8 = 0;
+
+// MATL's shading byte
+pub const SHADING_UNLIT: u8 = 0;
+pub const SHADING_FLAT: u8 = 1;
+pub const SHADING_LIT: u8 = 2;
+pub const SHADING_WIREFRAME: u8 = 3;
+
+// MATL's cull byte
+pub const CULL_BACK: u8 = 0;
+pub const CULL_FRONT: u8 = 1;
+pub const CULL_NONE: u8 = 2;
+
+// MRGB's value for a material with no rgb color
+pub const NO_RGB: u16 = 0xffff;
+
+pub type RawVector = [i32; 3];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FormatError {
+    BadMagic,
+    // the major version, made by a newer or older writer
+    UnsupportedVersion(u8),
+    // ran out of data part way through, the file is cut short
+    Truncated,
+    BadChecksum([u8; 4]),
+    MissingSection([u8; 4]),
+    // a MATL or UVCO section with no MESH before it, or an MRGB with no MATL
+    StraySection([u8; 4]),
+    // write only, a count in this section doesn't fit in the field it's saved in
+    TooBig([u8; 4]),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawPolygon {
+    pub material: u8,
+    pub verticies: [RawVector; 3],
+    pub normal: RawVector,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawLine {
+    pub color: u8,
+    pub verticies: [RawVector; 2],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawMaterial<'a> {
+    pub shading: u8,
+    pub cull: u8,
+    pub ramp: &'a [u8],
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RawMesh<'a> {
+    pub polygons: Vec<RawPolygon>,
+    pub lines: Vec<RawLine>,
+    // one per polygon when there's a UVCO section
+    pub uvs: Option<Vec<[[i32; 2]; 3]>>,
+    pub materials: Vec<RawMaterial<'a>>,
+    // one per material when there's an MRGB section
+    pub rgbs: Option<Vec<u16>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawObject {
+    pub mesh: u16,
+    pub pos: RawVector,
+    pub rot: RawVector,
+    pub scale: RawVector,
+}
+
+// ramps point into the file it was read from
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RawScene<'a> {
+    pub camera_pos: RawVector,
+    pub camera_rot: RawVector,
+    pub meshes: Vec<RawMesh<'a>>,
+    pub objects: Vec<RawObject>,
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, at: 0 }
+    }
+
+    // a slice straight out of the file, nothing gets copied
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], FormatError> {
+        // a hostile length can run past the end of the address space, not just the data
+        let end = self.at.checked_add(count).ok_or(FormatError::Truncated)?;
+        let bytes = self.data.get(self.at..end).ok_or(FormatError::Truncated)?;
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, FormatError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, FormatError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, FormatError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn num(&mut self) -> Result<i32, FormatError> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn vector(&mut self) -> Result<RawVector, FormatError> {
+        Ok([self.num()?, self.num()?, self.num()?])
+    }
+}
+
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Writer {
+        Writer { data: Vec::new() }
+    }
+
+    fn u8(&mut self, n: u8) {
+        self.data.push(n);
+    }
+
+    fn u16(&mut self, n: u16) {
+        self.data.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn num(&mut self, n: i32) {
+        self.data.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn vector(&mut self, v: RawVector) {
+        for n in v {
+            self.num(n);
+        }
+    }
+
+    // a count as a u16, rather than quietly chopping it and writing a broken file with a good checksum
+    fn count(&mut self, n: usize, tag: [u8; 4]) -> Result<(), FormatError> {
+        self.u16(u16::try_from(n).map_err(|_| FormatError::TooBig(tag))?);
+        Ok(())
+    }
+}
+
+// a tag and its payload, borrowed from the file
+pub type Section<'a> = ([u8; 4], &'a [u8]);
+
+// the checked payload of every section in order
+pub fn sections(data: &[u8]) -> Result<Vec<Section<'_>>, FormatError> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(4).map_err(|_| FormatError::BadMagic)? != MAGIC {
+        return Err(FormatError::BadMagic);
+    }
+    let major = reader.u8()?;
+    let _minor = reader.u8()?;
+    if major != MAJOR_VERSION {
+        return Err(FormatError::UnsupportedVersion(major));
+    }
+    let count = reader.u16()?;
+    let mut sections = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let b = reader.bytes(4)?;
+        let tag = [b[0], b[1], b[2], b[3]];
+        let length = reader.u32()? as usize;
+        let checksum = reader.u32()?;
+        let payload = reader.bytes(length)?;
+        if adler32(payload) != checksum {
+            return Err(FormatError::BadChecksum(tag));
+        }
+        sections.push((tag, payload));
+    }
+    Ok(sections)
+}
+
+fn read_mesh(payload: &[u8]) -> Result<RawMesh<'_>, FormatError> {
+    let mut reader = Reader::new(payload);
+    let polygon_count = reader.u16()?;
+    let line_count = reader.u16()?;
+    let mut mesh = RawMesh::default();
+    for _ in 0..polygon_count {
+        mesh.polygons.push(RawPolygon {
+            material: reader.u8()?,
+            verticies: [reader.vector()?, reader.vector()?, reader.vector()?],
+            normal: reader.vector()?,
+        });
+    }
+    for _ in 0..line_count {
+        mesh.lines.push(RawLine {
+            color: reader.u8()?,
+            verticies: [reader.vector()?, reader.vector()?],
+        });
+    }
+    Ok(mesh)
+}
+
+fn read_materials(payload: &[u8]) -> Result<Vec<RawMaterial<'_>>, FormatError> {
+    let mut reader = Reader::new(payload);
+    let count = reader.u16()?;
+    let mut materials = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let shading = reader.u8()?;
+        let cull = reader.u8()?;
+        let length = reader.u8()?;
+        materials.push(RawMaterial {
+            shading,
+            cull,
+            ramp: reader.bytes(length as usize)?,
+        });
+    }
+    Ok(materials)
+}
+
+impl<'a> RawScene<'a> {
+    pub fn read(data: &'a [u8]) -> Result<RawScene<'a>, FormatError> {
+        let mut camera = None;
+        let mut meshes: Vec<RawMesh<'a>> = Vec::new();
+        let mut objects = None;
+        for (tag, payload) in sections(data)? {
+            let mut reader = Reader::new(payload);
+            match &tag {
+                b"CAMR" => camera = Some((reader.vector()?, reader.vector()?)),
+                b"MESH" => meshes.push(read_mesh(payload)?),
+                b"UVCO" => {
+                    let mesh = meshes.last_mut().ok_or(FormatError::StraySection(tag))?;
+                    let mut uvs = Vec::with_capacity(mesh.polygons.len());
+                    for _ in 0..mesh.polygons.len() {
+                        let mut corners = [[0; 2]; 3];
+                        for corner in corners.iter_mut() {
+                            *corner = [reader.num()?, reader.num()?];
+                        }
+                        uvs.push(corners);
+                    }
+                    mesh.uvs = Some(uvs);
+                }
+                b"MATL" => {
+                    let mesh = meshes.last_mut().ok_or(FormatError::StraySection(tag))?;
+                    mesh.materials = read_materials(payload)?;
+                }
+                b"MRGB" => {
+                    let mesh = meshes
+                        .last_mut()
+                        .filter(|mesh| !mesh.materials.is_empty())
+                        .ok_or(FormatError::StraySection(tag))?;
+                    let mut rgbs = Vec::with_capacity(mesh.materials.len());
+                    for _ in 0..mesh.materials.len() {
+                        rgbs.push(reader.u16()?);
+                    }
+                    mesh.rgbs = Some(rgbs);
+                }
+                b"OBJS" => {
+                    let count = reader.u16()?;
+                    let mut placed = Vec::with_capacity(count as usize);
+                    for _ in 0..count {
+                        placed.push(RawObject {
+                            mesh: reader.u16()?,
+                            pos: reader.vector()?,
+                            rot: reader.vector()?,
+                            scale: reader.vector()?,
+                        });
+                    }
+                    objects = Some(placed);
+                }
+                _ => {}
+            }
+        }
+        let (camera_pos, camera_rot) = camera.ok_or(FormatError::MissingSection(*b"CAMR"))?;
+        Ok(RawScene {
+            camera_pos,
+            camera_rot,
+            meshes,
+            objects: objects.ok_or(FormatError::MissingSection(*b"OBJS"))?,
+        })
+    }
+
+    // each mesh's sections come straight after its MESH, empty MATLs and missing UVCOs and MRGBs are left out
+    pub fn write(&self) -> Result<Vec<u8>, FormatError> {
+        let mut sections: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+
+        let mut camera = Writer::new();
+        camera.vector(self.camera_pos);
+        camera.vector(self.camera_rot);
+        sections.push((*b"CAMR", camera.data));
+
+        for mesh in self.meshes.iter() {
+            let mut section = Writer::new();
+            section.count(mesh.polygons.len(), *b"MESH")?;
+            section.count(mesh.lines.len(), *b"MESH")?;
+            for poly in mesh.polygons.iter() {
+                section.u8(poly.material);
+                for v in poly.verticies {
+                    section.vector(v);
+                }
+                section.vector(poly.normal);
+            }
+            for line in mesh.lines.iter() {
+                section.u8(line.color);
+                section.vector(line.verticies[0]);
+                section.vector(line.verticies[1]);
+            }
+            sections.push((*b"MESH", section.data));
+
+            if let Some(uvs) = &mesh.uvs {
+                let mut section = Writer::new();
+                for corners in uvs {
+                    for [u, v] in corners {
+                        section.num(*u);
+                        section.num(*v);
+                    }
+                }
+                sections.push((*b"UVCO", section.data));
+            }
+
+            if !mesh.materials.is_empty() {
+                let mut section = Writer::new();
+                section.count(mesh.materials.len(), *b"MATL")?;
+                for material in mesh.materials.iter() {
+                    section.u8(material.shading);
+                    section.u8(material.cull);
+                    let length = u8::try_from(material.ramp.len())
+                        .map_err(|_| FormatError::TooBig(*b"MATL"))?;
+                    section.u8(length);
+                    section.data.extend_from_slice(material.ramp);
+                }
+                sections.push((*b"MATL", section.data));
+            }
+
+            if let Some(rgbs) = &mesh.rgbs {
+                let mut section = Writer::new();
+                for &rgb in rgbs {
+                    section.u16(rgb);
+                }
+                sections.push((*b"MRGB", section.data));
+            }
+        }
+
+        let mut objects = Writer::new();
+        objects.count(self.objects.len(), *b"OBJS")?;
+        for object in self.objects.iter() {
+            objects.u16(object.mesh);
+            objects.vector(object.pos);
+            objects.vector(object.rot);
+            objects.vector(object.scale);
+        }
+        sections.push((*b"OBJS", objects.data));
+
+        let mut file = Writer::new();
+        file.data.extend_from_slice(&MAGIC);
+        file.u8(MAJOR_VERSION);
+        file.u8(MINOR_VERSION);
+        file.count(sections.len(), MAGIC)?;
+        for (tag, payload) in sections {
+            let length = u32::try_from(payload.len()).map_err(|_| FormatError::TooBig(tag))?;
+            file.data.extend_from_slice(&tag);
+            file.data.extend_from_slice(&length.to_le_bytes());
+            file.data
+                .extend_from_slice(&adler32(&payload).to_le_bytes());
+            file.data.extend_from_slice(&payload);
+        }
+        Ok(file.data)
+    }
+}
@@ -0,0 +1,138 @@
+/*
+rigid skinning, every vertex follows exactly one bone. verticies are modelled in the bind pose, and each
+frame the bones' current local transforms get chained down the hierarchy to move them.
+*/
+
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use crate::geometry::*;
+use crate::matrix::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bone {
+    // bones have to come after their parent in the list
+    pub parent: Option<usize>,
+    // bind pose, relative to the parent
+    pub pos: Vector3D<DefaultNum>,
+    pub rot: Vector3D<DefaultNum>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Pose {
+    pub pos: Vector3D<DefaultNum>,
+    pub rot: Vector3D<DefaultNum>,
+}
+
+pub struct Skeleton {
+    pub bones: Cow<'static, [Bone]>,
+    // what the animation sets each frame, relative to the parent. starts at the bind pose
+    pub pose: Vec<Pose>,
+    inverse_bind: Vec<Matrix>,
+}
+
+impl Skeleton {
+    pub fn new(bones: impl Into<Cow<'static, [Bone]>>) -> Skeleton {
+        let bones: Cow<'static, [Bone]> = bones.into();
+        for (i, bone) in bones.iter().enumerate() {
+            if bone.parent.is_some_and(|parent| parent >= i) {
+                panic!("Bones have to come after their parent.");
+            }
+        }
+        let pose: Vec<Pose> = bones
+            .iter()
+            .map(|bone| Pose {
+                pos: bone.pos,
+                rot: bone.rot,
+            })
+            .collect();
+        let mut skeleton = Skeleton {
+            bones,
+            pose,
+            inverse_bind: Vec::new(),
+        };
+        skeleton.inverse_bind = skeleton
+            .world_matrices()
+            .iter()
+            .map(Matrix::rigid_inverse)
+            .collect();
+        skeleton
+    }
+
+    pub fn reset_pose(&mut self) {
+        for (pose, bone) in self.pose.iter_mut().zip(self.bones.iter()) {
+            pose.pos = bone.pos;
+            pose.rot = bone.rot;
+        }
+    }
+
+    // where each bone is in object space in the current pose
+    pub fn world_matrices(&self) -> Vec<Matrix> {
+        let mut world: Vec<Matrix> = Vec::with_capacity(self.bones.len());
+        for (bone, pose) in self.bones.iter().zip(self.pose.iter()) {
+            let local = Matrix::affine(pose.rot, pose.pos);
+            world.push(match bone.parent {
+                Some(parent) => local.mul(world[parent]),
+                None => local,
+            });
+        }
+        world
+    }
+
+    // takes a bind pose vertex to where its bone has moved it, one per bone. work these out once per frame
+    pub fn skin_matrices(&self) -> Vec<Matrix> {
+        self.world_matrices()
+            .iter()
+            .zip(self.inverse_bind.iter())
+            .map(|(world, inverse_bind)| inverse_bind.mul(*world))
+            .collect()
+    }
+}
+
+pub struct Skin {
+    pub skeleton: Skeleton,
+    // the bone for each vertex of each polygon, in the same order as the mesh
+    pub bones: Cow<'static, [[u8; 3]]>,
+}
+
+impl Mesh {
+    pub fn set_skin(&mut self, skin: Skin) {
+        if skin.bones.len() != self.verticies.len() {
+            panic!("Skin doesn't match the mesh.");
+        }
+        if skin
+            .bones
+            .iter()
+            .flatten()
+            .any(|bone| *bone as usize >= skin.skeleton.bones.len())
+        {
+            panic!("Skin uses a bone that doesn't exist.");
+        }
+        self.skin = Some(skin);
+    }
+
+    // moves a bind pose polygon by its bones, matrices come from Skeleton::skin_matrices
+    pub fn skinned(&self, poly: Polygon, index: usize, matrices: &[Matrix]) -> Polygon {
+        let bones = match &self.skin {
+            Some(skin) => skin.bones[index],
+            None => return poly,
+        };
+        let verticies = [
+            matrices[bones[0] as usize].transform_point(poly.verticies[0]),
+            matrices[bones[1] as usize].transform_point(poly.verticies[1]),
+            matrices[bones[2] as usize].transform_point(poly.verticies[2]),
+        ];
+        // a polygon on one bone just turns with it, one stretched between bones changes shape
+        let normal = if bones[0] == bones[1] && bones[1] == bones[2] {
+            matrices[bones[0] as usize].rotate(poly.normal)
+        } else {
+            face_normal(&verticies)
+        };
+        Polygon {
+            verticies,
+            normal,
+            ..poly
+        }
+    }
+}
@@ -0,0 +1,358 @@
+/*
+3d text for title screens. glyphs come from a built in stroke font, each one a few lines on a 4x6 grid. every
+line of a stroke gets pushed out into a box as thick as the style's weight and as deep as its depth, with square
+ends so strokes meet without gaps at the corners. the boxes overlap where strokes join rather than being cut to
+fit, so each one is closed on its own and the mesh stays cheap to build. boxes meeting at a corner can share
+an edge there, which the validator calls non-manifold.
+*/
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use agb::fixnum::num;
+
+use crate::geometry::*;
+use crate::material::Material;
+use crate::matrix::*;
+
+const GLYPH_WIDTH: i32 = 4;
+const GLYPH_HEIGHT: i32 = 6;
+
+// the text mesh's materials are the style's face then side
+const FACE: u8 = 0;
+const SIDE: u8 = 1;
+
+type Stroke = &'static [(i8, i8)];
+
+// lines joining grid points, y goes down. a stroke with one point is a dot
+fn glyph(c: char) -> &'static [Stroke] {
+    const O: Stroke = &[
+        (1, 0),
+        (3, 0),
+        (4, 1),
+        (4, 5),
+        (3, 6),
+        (1, 6),
+        (0, 5),
+        (0, 1),
+        (1, 0),
+    ];
+    const P: Stroke = &[(0, 6), (0, 0), (3, 0), (4, 1), (4, 2), (3, 3), (0, 3)];
+    match c.to_ascii_uppercase() {
+        'A' => &[&[(0, 6), (0, 2), (2, 0), (4, 2), (4, 6)], &[(0, 3), (4, 3)]],
+        'B' => &[
+            &[
+                (0, 3),
+                (3, 3),
+                (4, 4),
+                (4, 5),
+                (3, 6),
+                (0, 6),
+                (0, 0),
+                (3, 0),
+            ],
+            &[(3, 0), (4, 1), (4, 2), (3, 3)],
+        ],
+        'C' => &[&[(4, 0), (1, 0), (0, 1), (0, 5), (1, 6), (4, 6)]],
+        'D' => &[&[(0, 0), (0, 6), (2, 6), (4, 4), (4, 2), (2, 0), (0, 0)]],
+        'E' => &[&[(4, 0), (0, 0), (0, 6), (4, 6)], &[(0, 3), (3, 3)]],
+        'F' => &[&[(4, 0), (0, 0), (0, 6)], &[(0, 3), (3, 3)]],
+        'G' => &[&[
+            (4, 1),
+            (3, 0),
+            (1, 0),
+            (0, 1),
+            (0, 5),
+            (1, 6),
+            (3, 6),
+            (4, 5),
+            (4, 3),
+            (2, 3),
+        ]],
+        'H' => &[&[(0, 0), (0, 6)], &[(4, 0), (4, 6)], &[(0, 3), (4, 3)]],
+        'I' => &[&[(1, 0), (3, 0)], &[(2, 0), (2, 6)], &[(1, 6), (3, 6)]],
+        'J' => &[&[(1, 0), (4, 0)], &[(3, 0), (3, 5), (2, 6), (1, 6), (0, 5)]],
+        'K' => &[&[(0, 0), (0, 6)], &[(4, 0), (0, 4)], &[(1, 3), (4, 6)]],
+        'L' => &[&[(0, 0), (0, 6), (4, 6)]],
+        'M' => &[&[(0, 6), (0, 0), (2, 3), (4, 0), (4, 6)]],
+        'N' => &[&[(0, 6), (0, 0), (4, 6), (4, 0)]],
+        'O' => &[O],
+        'P' => &[P],
+        'Q' => &[O, &[(2, 4), (4, 6)]],
+        'R' => &[P, &[(2, 3), (4, 6)]],
+        'S' => &[&[
+            (4, 1),
+            (3, 0),
+            (1, 0),
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (3, 3),
+            (4, 4),
+            (4, 5),
+            (3, 6),
+            (1, 6),
+            (0, 5),
+        ]],
+        'T' => &[&[(0, 0), (4, 0)], &[(2, 0), (2, 6)]],
+        'U' => &[&[(0, 0), (0, 5), (1, 6), (3, 6), (4, 5), (4, 0)]],
+        'V' => &[&[(0, 0), (2, 6), (4, 0)]],
+        'W' => &[&[(0, 0), (1, 6), (2, 3), (3, 6), (4, 0)]],
+        'X' => &[&[(0, 0), (4, 6)], &[(4, 0), (0, 6)]],
+        'Y' => &[&[(0, 0), (2, 3), (4, 0)], &[(2, 3), (2, 6)]],
+        'Z' => &[&[(0, 0), (4, 0), (0, 6), (4, 6)]],
+        '0' => &[O, &[(4, 1), (0, 5)]],
+        '1' => &[&[(1, 1), (2, 0), (2, 6)], &[(1, 6), (3, 6)]],
+        '2' => &[&[(0, 1), (1, 0), (3, 0), (4, 1), (4, 2), (0, 6), (4, 6)]],
+        '3' => &[&[
+            (0, 0),
+            (4, 0),
+            (2, 2),
+            (3, 2),
+            (4, 3),
+            (4, 5),
+            (3, 6),
+            (1, 6),
+            (0, 5),
+        ]],
+        '4' => &[&[(3, 6), (3, 0), (0, 4), (4, 4)]],
+        '5' => &[&[
+            (4, 0),
+            (0, 0),
+            (0, 3),
+            (3, 3),
+            (4, 4),
+            (4, 5),
+            (3, 6),
+            (0, 6),
+        ]],
+        '6' => &[&[
+            (3, 0),
+            (1, 0),
+            (0, 1),
+            (0, 5),
+            (1, 6),
+            (3, 6),
+            (4, 5),
+            (4, 4),
+            (3, 3),
+            (0, 3),
+        ]],
+        '7' => &[&[(0, 0), (4, 0), (1, 6)]],
+        '8' => &[
+            &[
+                (1, 3),
+                (0, 2),
+                (0, 1),
+                (1, 0),
+                (3, 0),
+                (4, 1),
+                (4, 2),
+                (3, 3),
+                (1, 3),
+            ],
+            &[
+                (1, 3),
+                (0, 4),
+                (0, 5),
+                (1, 6),
+                (3, 6),
+                (4, 5),
+                (4, 4),
+                (3, 3),
+            ],
+        ],
+        '9' => &[&[
+            (4, 3),
+            (1, 3),
+            (0, 2),
+            (0, 1),
+            (1, 0),
+            (3, 0),
+            (4, 1),
+            (4, 5),
+            (3, 6),
+            (1, 6),
+        ]],
+        '!' => &[&[(2, 0), (2, 4)], &[(2, 6)]],
+        '?' => &[
+            &[(0, 1), (1, 0), (3, 0), (4, 1), (4, 2), (2, 3), (2, 4)],
+            &[(2, 6)],
+        ],
+        '.' => &[&[(2, 6)]],
+        '-' => &[&[(1, 3), (3, 3)]],
+        _ => &[],
+    }
+}
+
+pub struct TextStyle {
+    // height of a capital letter
+    pub size: DefaultNum,
+    // how thick the strokes are
+    pub weight: DefaultNum,
+    // how far the letters stick out, front to back
+    pub depth: DefaultNum,
+    // gap between characters
+    pub spacing: DefaultNum,
+    pub face: Material,
+    pub side: Material,
+}
+
+impl TextStyle {
+    pub fn new() -> TextStyle {
+        TextStyle {
+            size: num!(24.0),
+            weight: num!(4.0),
+            depth: num!(8.0),
+            spacing: num!(4.0),
+            face: Material::solid(&[100]),
+            side: Material::solid(&[4]),
+        }
+    }
+}
+
+// two triangles, wound so they face out along the given direction whatever order the corners come in
+fn push_quad(
+    polygons: &mut Vec<Polygon>,
+    corners: [Vector3D<DefaultNum>; 4],
+    outward: Vector3D<DefaultNum>,
+    material: u8,
+) {
+    for tri in [
+        [corners[0], corners[1], corners[2]],
+        [corners[0], corners[2], corners[3]],
+    ] {
+        let mut poly = Polygon::new(material, tri);
+        if poly.normal.dot(outward) < num!(0.0) {
+            poly.verticies.swap(1, 2);
+            poly.normal = -poly.normal;
+        }
+        polygons.push(poly);
+    }
+}
+
+// one line of a stroke as a box, lengthened by half the weight at each end so joins and dots come out square
+fn push_segment(
+    polygons: &mut Vec<Polygon>,
+    a: Vector3D<DefaultNum>,
+    b: Vector3D<DefaultNum>,
+    weight: DefaultNum,
+    depth: DefaultNum,
+) {
+    let half = weight / 2;
+    let length = (b - a).length();
+    let along = if length > num!(0.0) {
+        Vector3D::new(div(b.x - a.x, length), div(b.y - a.y, length), num!(0.0))
+    } else {
+        Vector3D::new(num!(1.0), num!(0.0), num!(0.0))
+    };
+    let across = Vector3D::new(-along.y, along.x, num!(0.0));
+    let (start, end) = (a - along * half, b + along * half);
+    let front = Vector3D::new(num!(0.0), num!(0.0), -depth / 2);
+    let back = -front;
+
+    // the outline of the box in the glyph's plane, going around it
+    let outline = [
+        start - across * half,
+        end - across * half,
+        end + across * half,
+        start + across * half,
+    ];
+    push_quad(
+        polygons,
+        outline.map(|p| p + front),
+        Vector3D::new(num!(0.0), num!(0.0), num!(-1.0)),
+        FACE,
+    );
+    push_quad(
+        polygons,
+        outline.map(|p| p + back),
+        Vector3D::new(num!(0.0), num!(0.0), num!(1.0)),
+        FACE,
+    );
+    for (i, outward) in [-across, along, across, -along].into_iter().enumerate() {
+        let (p, q) = (outline[i], outline[(i + 1) % 4]);
+        push_quad(
+            polygons,
+            [p + front, q + front, q + back, p + back],
+            outward,
+            SIDE,
+        );
+    }
+}
+
+// a standard mesh centered on the origin, reading left to right along x with the front facing -z
+pub fn text_mesh(text: &str, style: &TextStyle) -> Mesh {
+    let unit = style.size / GLYPH_HEIGHT;
+    let advance = unit * GLYPH_WIDTH + style.spacing;
+    let count = text.chars().count() as i32;
+    let width = advance * count - style.spacing;
+    let left = -width / 2;
+    let top = -style.size / 2;
+
+    let mut polygons = Vec::new();
+    for (index, c) in text.chars().enumerate() {
+        let origin = left + advance * index as i32;
+        let point = |&(x, y): &(i8, i8)| {
+            Vector3D::new(origin + unit * x as i32, top + unit * y as i32, num!(0.0))
+        };
+        for stroke in glyph(c) {
+            if let [dot] = stroke {
+                push_segment(
+                    &mut polygons,
+                    point(dot),
+                    point(dot),
+                    style.weight,
+                    style.depth,
+                );
+            }
+            for pair in stroke.windows(2) {
+                push_segment(
+                    &mut polygons,
+                    point(&pair[0]),
+                    point(&pair[1]),
+                    style.weight,
+                    style.depth,
+                );
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(
+        polygons,
+        Vector3D::new(num!(0.0), num!(0.0), num!(0.0)),
+        Vector3D::new(num!(0.0), num!(0.0), num!(0.0)),
+    );
+    mesh.materials = vec![style.face.clone(), style.side.clone()].into();
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+
+    #[test_case]
+    fn strokes_become_closed_boxes(_gba: &mut agb::Gba) {
+        let style = TextStyle::new();
+        // three strokes each, twelve triangles a box. the space is empty but still takes up room
+        let mesh = text_mesh("H I", &style);
+        assert_eq!(mesh.verticies.len(), 6 * 12);
+        assert_eq!(mesh.aabb.min.x, -mesh.aabb.max.x);
+        assert_eq!(mesh.aabb.min.z, -style.depth / 2);
+        assert!(validate(&mesh.verticies)
+            .iter()
+            .all(|issue| !issue.is_error()));
+    }
+
+    #[test_case]
+    fn dots_are_square(_gba: &mut agb::Gba) {
+        let style = TextStyle::new();
+        let mesh = text_mesh(".", &style);
+        assert_eq!(mesh.verticies.len(), 12);
+        let size = mesh.aabb.max - mesh.aabb.min;
+        assert_eq!(size.x, style.weight);
+        assert_eq!(size.y, style.weight);
+    }
+}
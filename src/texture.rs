@@ -0,0 +1,85 @@
+/*
+8 bit paletted textures. sizes have to be powers of two so wrapping a coordinate is just a mask.
+embed one with include_texture!, build.rs makes them from indexed pngs in assets/textures.
+*/
+
+extern crate alloc;
+use alloc::borrow::Cow;
+
+use agb::fixnum::Vector2D;
+
+use crate::matrix::*;
+
+// palette indicies, row by row
+#[derive(Clone, Debug)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Cow<'static, [u8]>,
+    // the colors the png was drawn with as little endian Rgb15, load them into the palette for the texels to look right
+    pub palette: Cow<'static, [u8]>,
+}
+
+impl Texture {
+    pub fn new(
+        width: usize,
+        height: usize,
+        texels: impl Into<Cow<'static, [u8]>>,
+        palette: impl Into<Cow<'static, [u8]>>,
+    ) -> Texture {
+        let texels = texels.into();
+        check(width, height, texels.len());
+        Texture {
+            width,
+            height,
+            texels,
+            palette: palette.into(),
+        }
+    }
+
+    // the format build.rs writes: width, height and palette length as little endian u16s, the palette, then the
+    // texels. const so a texture can go straight into a static and be pointed at by materials
+    pub const fn from_bytes(bytes: &'static [u8]) -> Texture {
+        let width = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        let height = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+        let colors = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+        if bytes.len() < 6 + colors * 2 {
+            panic!("Texture data is the wrong size.");
+        }
+        let (_, rest) = bytes.split_at(6);
+        let (palette, texels) = rest.split_at(colors * 2);
+        check(width, height, texels.len());
+        Texture {
+            width,
+            height,
+            texels: Cow::Borrowed(texels),
+            palette: Cow::Borrowed(palette),
+        }
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = u16> + '_ {
+        self.palette
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.texels[(y & (self.height - 1)) * self.width + (x & (self.width - 1))]
+    }
+
+    // uv from 0 to 1 covers the texture once, outside that it repeats
+    pub fn sample(&self, uv: Vector2D<DefaultNum>) -> u8 {
+        let x = (uv.x * self.width as i32).floor();
+        let y = (uv.y * self.height as i32).floor();
+        self.get(x as usize, y as usize)
+    }
+}
+
+const fn check(width: usize, height: usize, texels: usize) {
+    if !width.is_power_of_two() || !height.is_power_of_two() {
+        panic!("Texture sizes have to be powers of two.");
+    }
+    if texels != width * height {
+        panic!("Texture data is the wrong size.");
+    }
+}
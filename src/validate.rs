@@ -0,0 +1,168 @@
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::geometry::*;
+use crate::matrix::*;
+use crate::mesh_check::{self, Issue, RawPoint};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MeshIssue {
+    // both polygons walk their shared edge in the same direction, so one of them faces the wrong way
+    InconsistentWinding(usize, usize),
+    ZeroArea(usize),
+    DuplicateFace(usize, usize),
+    // more than two polygons share the edge between these two points
+    NonManifoldEdge([Vector3D<DefaultNum>; 2]),
+    // only one polygon uses this edge, the mesh has a hole in it
+    OpenEdge(usize),
+}
+
+fn raw(polygons: &[Polygon]) -> Vec<[RawPoint; 3]> {
+    polygons
+        .iter()
+        .map(|poly| {
+            poly.verticies
+                .map(|v| [v.x.to_raw(), v.y.to_raw(), v.z.to_raw()])
+        })
+        .collect()
+}
+
+// the checks themselves are in mesh_check.rs, shared with build.rs
+pub fn validate(polygons: &[Polygon]) -> Vec<MeshIssue> {
+    let point = |p: RawPoint| {
+        Vector3D::new(
+            DefaultNum::from_raw(p[0]),
+            DefaultNum::from_raw(p[1]),
+            DefaultNum::from_raw(p[2]),
+        )
+    };
+    mesh_check::check(&raw(polygons))
+        .into_iter()
+        .map(|issue| match issue {
+            Issue::InconsistentWinding(a, b) => MeshIssue::InconsistentWinding(a, b),
+            Issue::ZeroArea(i) => MeshIssue::ZeroArea(i),
+            Issue::DuplicateFace(a, b) => MeshIssue::DuplicateFace(a, b),
+            Issue::NonManifoldEdge([a, b]) => MeshIssue::NonManifoldEdge([point(a), point(b)]),
+            Issue::OpenEdge(i) => MeshIssue::OpenEdge(i),
+        })
+        .collect()
+}
+
+// flips polygons so they agree with their neighbours, starting from the first polygon of each connected piece.
+// returns how many were flipped. it can only make winding consistent, not decide which side is outside.
+pub fn fix_winding(polygons: &mut [Polygon]) -> usize {
+    let (faces, _) = mesh_check::weld(&raw(polygons));
+    let edges = mesh_check::edges(&faces);
+    let mut neighbours: Vec<Vec<(usize, bool)>> = vec![Vec::new(); polygons.len()];
+    for users in edges.values() {
+        if let [(a, a_dir), (b, b_dir)] = users.as_slice() {
+            // true when the pair currently disagrees
+            neighbours[*a].push((*b, a_dir == b_dir));
+            neighbours[*b].push((*a, a_dir == b_dir));
+        }
+    }
+
+    let mut flipped = vec![false; polygons.len()];
+    let mut visited = vec![false; polygons.len()];
+    let mut queue = VecDeque::new();
+    for start in 0..polygons.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(face) = queue.pop_front() {
+            for &(other, disagrees) in &neighbours[face] {
+                if !visited[other] {
+                    visited[other] = true;
+                    flipped[other] = flipped[face] ^ disagrees;
+                    queue.push_back(other);
+                }
+            }
+        }
+    }
+
+    let mut count = 0;
+    for (poly, flip) in polygons.iter_mut().zip(flipped) {
+        if flip {
+            poly.verticies.swap(1, 2);
+            poly.normal = -poly.normal;
+            count += 1;
+        }
+    }
+    count
+}
+
+impl MeshIssue {
+    // open and non-manifold edges are fine on purpose sometimes, like a flat ground or blocks touching at a
+    // corner. the rest always draw wrong
+    pub fn is_error(&self) -> bool {
+        !matches!(self, MeshIssue::OpenEdge(_) | MeshIssue::NonManifoldEdge(_))
+    }
+}
+
+// debug builds check every mesh when the scene loads and print whatever is wrong to the mgba log
+pub fn report(name: &str, polygons: &[Polygon]) {
+    for issue in validate(polygons) {
+        agb::println!("mesh {}: {:?}", name, issue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn closed_cube(_gba: &mut agb::Gba) {
+        assert_eq!(validate(&cube().verticies), vec![]);
+    }
+
+    #[test_case]
+    fn flipped_face(_gba: &mut agb::Gba) {
+        let mut polygons = cube().verticies.to_vec();
+        polygons[3].verticies.swap(1, 2);
+        let issues = validate(&polygons);
+        assert!(!issues.is_empty());
+        assert!(issues.iter().all(|issue| matches!(
+            issue,
+            MeshIssue::InconsistentWinding(a, b) if *a == 3 || *b == 3
+        )));
+    }
+
+    #[test_case]
+    fn open_edge(_gba: &mut agb::Gba) {
+        let mut polygons = cube().verticies.to_vec();
+        polygons.remove(0);
+        let issues = validate(&polygons);
+        // the missing triangle leaves its three edges with one user each
+        assert_eq!(issues.len(), 3);
+        assert!(issues
+            .iter()
+            .all(|issue| matches!(issue, MeshIssue::OpenEdge(_)) && !issue.is_error()));
+    }
+
+    #[test_case]
+    fn zero_area_and_duplicates(_gba: &mut agb::Gba) {
+        let mut polygons = cube().verticies.to_vec();
+        let copy = polygons[0];
+        polygons.push(copy);
+        let mut flat = polygons[1];
+        flat.verticies[2] = flat.verticies[1];
+        polygons.push(flat);
+        let issues = validate(&polygons);
+        assert!(issues.contains(&MeshIssue::DuplicateFace(0, 12)));
+        assert!(issues.contains(&MeshIssue::ZeroArea(13)));
+    }
+
+    #[test_case]
+    fn fix_winding_repairs(_gba: &mut agb::Gba) {
+        let mut polygons = cube().verticies.to_vec();
+        polygons[3].verticies.swap(1, 2);
+        polygons[3].normal = -polygons[3].normal;
+        assert_eq!(fix_winding(&mut polygons), 1);
+        assert_eq!(validate(&polygons), vec![]);
+        assert_eq!(polygons[3].normal, cube().verticies[3].normal);
+    }
+}